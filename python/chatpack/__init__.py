@@ -5,11 +5,25 @@ Parse and convert chat exports from Telegram, WhatsApp, Instagram, and Discord
 into LLM-friendly formats. Built with Rust for maximum performance.
 """
 
+import functools
+
+from . import _chatpack  # type: ignore
 from ._chatpack import (  # type: ignore
     # Message types
     PyMessage,
+    PyPlatform,
     PyFilterConfig,
+    PyMergeConfig,
     PyOutputConfig,
+    PyDocument,
+    PySession,
+    PyRedactor,
+    # Exceptions
+    ChatpackError,
+    ParseError,
+    InvalidDateError,
+    UnsupportedFormatError,
+    ChatpackIOError,
     # Parsers (classes)
     TelegramParser,
     WhatsAppParser,
@@ -25,15 +39,65 @@ from ._chatpack import (  # type: ignore
     parse_whatsapp,
     parse_instagram,
     parse_discord,
+    parse_auto,
+    detect_platform,
+    parse_many,
+    referenced_media,
+    # Arrow / DataFrame export
+    to_arrow,
+    to_pandas,
+    to_polars,
+    parse_telegram_to_arrow,
+    parse_whatsapp_to_arrow,
+    parse_instagram_to_arrow,
+    parse_discord_to_arrow,
+    parse_auto_to_arrow,
     # Utilities
     merge_consecutive,
     apply_filters,
+    count_tokens,
+    chunk_by_tokens,
+    to_documents,
+    split_sessions,
+    redact,
+    # Output writers
+    write_csv,
+    write_json,
+    write_jsonl,
+    to_csv_string,
+    to_json_string,
+    to_jsonl_string,
+    write_parquet,
+    write_parquet_stream,
 )
 
 # Compatibility aliases (Делаем красивые имена для пользователей)
 Message = PyMessage
+Platform = PyPlatform
 FilterConfig = PyFilterConfig
+MergeConfig = PyMergeConfig
 OutputConfig = PyOutputConfig
+Document = PyDocument
+Session = PySession
+Redactor = PyRedactor
+
+
+def _coroutine(native):
+    """Wrap a native function returning an asyncio.Future into a coroutine function,
+    so the result works with asyncio.run() and create_task()"""
+
+    @functools.wraps(native)
+    async def wrapper(*args, **kwargs):
+        return await native(*args, **kwargs)
+
+    return wrapper
+
+
+async_parse_telegram = _coroutine(_chatpack.async_parse_telegram)
+async_parse_whatsapp = _coroutine(_chatpack.async_parse_whatsapp)
+async_parse_instagram = _coroutine(_chatpack.async_parse_instagram)
+async_parse_discord = _coroutine(_chatpack.async_parse_discord)
+async_parse_auto = _coroutine(_chatpack.async_parse_auto)
 
 __version__ = "0.1.0"
 
@@ -41,10 +105,26 @@ __all__ = [
     # Types
     "Message",
     "PyMessage",
+    "Platform",
+    "PyPlatform",
     "FilterConfig",
     "PyFilterConfig",
+    "MergeConfig",
+    "PyMergeConfig",
     "OutputConfig",
     "PyOutputConfig",
+    "Document",
+    "PyDocument",
+    "Session",
+    "PySession",
+    "Redactor",
+    "PyRedactor",
+    # Exceptions
+    "ChatpackError",
+    "ParseError",
+    "InvalidDateError",
+    "UnsupportedFormatError",
+    "ChatpackIOError",
     # Parsers
     "TelegramParser",
     "WhatsAppParser",
@@ -60,6 +140,37 @@ __all__ = [
     "parse_whatsapp",
     "parse_instagram",
     "parse_discord",
+    "parse_auto",
+    "async_parse_telegram",
+    "async_parse_whatsapp",
+    "async_parse_instagram",
+    "async_parse_discord",
+    "async_parse_auto",
+    "detect_platform",
+    "parse_many",
+    "referenced_media",
+    "to_arrow",
+    "to_pandas",
+    "to_polars",
+    "parse_telegram_to_arrow",
+    "parse_whatsapp_to_arrow",
+    "parse_instagram_to_arrow",
+    "parse_discord_to_arrow",
+    "parse_auto_to_arrow",
     "merge_consecutive",
     "apply_filters",
+    "count_tokens",
+    "chunk_by_tokens",
+    "to_documents",
+    "split_sessions",
+    "redact",
+    # Output
+    "write_csv",
+    "write_json",
+    "write_jsonl",
+    "to_csv_string",
+    "to_json_string",
+    "to_jsonl_string",
+    "write_parquet",
+    "write_parquet_stream",
 ]
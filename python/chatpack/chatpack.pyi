@@ -1,23 +1,92 @@
 """Type stubs for chatpack"""
 
-from typing import List, Optional, Iterator, Dict, Any
-from datetime import datetime
+import os
+from typing import (
+    IO,
+    Any,
+    AsyncIterator,
+    Callable,
+    Coroutine,
+    Dict,
+    Iterator,
+    List,
+    Optional,
+    Tuple,
+    Union,
+)
+from datetime import datetime, timedelta
+from enum import Enum
+
+# Anything the parse entry points accept: a path, raw bytes or a file-like object
+Source = Union[str, "os.PathLike[str]", bytes, bytearray, memoryview, IO[Any]]
+
+# Built-in tokenizer name ("cl100k", "o200k" or "chars"), an object with
+# .encode(text) such as a tiktoken Encoding, or a callable returning a token count
+TokenizerLike = Union[str, Any, Callable[[str], int]]
+
+# Aware datetime (any timezone), naive datetime (taken as UTC) or an RFC 3339 string
+TimestampLike = Union[datetime, str]
+
+class Platform(Enum):
+    """Source platform of a message"""
+
+    TELEGRAM = 0
+    WHATSAPP = 1
+    INSTAGRAM = 2
+    DISCORD = 3
+
+    @staticmethod
+    def from_str(name: str) -> "Platform":
+        """Parse a platform name or alias ("telegram", "tg", "wa", ...)"""
+        ...
+    @property
+    def value(self) -> str: ...  # type: ignore[override]
 
 class Message:
     """Universal message representation across all platforms"""
 
     sender: str
     content: str
-    timestamp: Optional[str]
-    platform: Optional[str]
+    id: Optional[int]
+    reply_to: Optional[int]
+    conversation: Optional[str]
+    """Conversation title (Instagram directory exports)"""
 
     def __init__(
         self,
         sender: str,
         content: str,
-        timestamp: Optional[str] = None,
-        platform: Optional[str] = None,
+        timestamp: Optional[TimestampLike] = None,
+        platform: Optional[Union[str, Platform]] = None,
+        id: Optional[int] = None,
+        reply_to: Optional[int] = None,
+        edited: Optional[TimestampLike] = None,
+        conversation: Optional[str] = None,
     ) -> None: ...
+    @property
+    def timestamp(self) -> Optional[datetime]:
+        """Timezone-aware (UTC) datetime"""
+        ...
+    @timestamp.setter
+    def timestamp(self, value: Optional[TimestampLike]) -> None: ...
+    @property
+    def timestamp_str(self) -> Optional[str]:
+        """Timestamp as an RFC 3339 string, e.g. 2024-01-15T10:30:00+00:00"""
+        ...
+    @property
+    def epoch_ms(self) -> Optional[int]:
+        """Timestamp as milliseconds since the Unix epoch"""
+        ...
+    @property
+    def edited(self) -> Optional[datetime]: ...
+    @edited.setter
+    def edited(self, value: Optional[TimestampLike]) -> None: ...
+    @property
+    def platform(self) -> Optional[str]: ...
+    @platform.setter
+    def platform(self, value: Optional[Union[str, Platform]]) -> None: ...
+    @property
+    def platform_enum(self) -> Optional[Platform]: ...
     def to_dict(self) -> Dict[str, Any]: ...
     def __repr__(self) -> str: ...
     def __str__(self) -> str: ...
@@ -30,6 +99,7 @@ class FilterConfig:
     sender: Optional[str]
     date_from: Optional[str]
     date_to: Optional[str]
+    truncate: bool
 
     def __init__(
         self,
@@ -38,13 +108,32 @@ class FilterConfig:
         sender: Optional[str] = None,
         date_from: Optional[str] = None,
         date_to: Optional[str] = None,
+        truncate: bool = False,
     ) -> None: ...
     def with_min_length(self, length: int) -> "FilterConfig": ...
     def with_max_length(self, length: int) -> "FilterConfig": ...
+    def with_truncate(self) -> "FilterConfig": ...
     def with_sender(self, sender: str) -> "FilterConfig": ...
     def with_date_from(self, date: str) -> "FilterConfig": ...
     def with_date_to(self, date: str) -> "FilterConfig": ...
 
+class MergeConfig:
+    """Configuration for merging consecutive messages"""
+
+    time_threshold: Optional[int]
+    max_merged_length: Optional[int]
+    separator: str
+
+    def __init__(
+        self,
+        time_threshold: Optional[int] = 300,
+        max_merged_length: Optional[int] = None,
+        separator: str = "\n",
+    ) -> None: ...
+    def with_time_threshold(self, seconds: int) -> "MergeConfig": ...
+    def with_max_merged_length(self, length: int) -> "MergeConfig": ...
+    def with_separator(self, separator: str) -> "MergeConfig": ...
+
 class OutputConfig:
     """Configuration for output formatting"""
 
@@ -57,102 +146,478 @@ class OutputConfig:
     def with_timestamps(self) -> "OutputConfig": ...
     def with_platform(self) -> "OutputConfig": ...
 
+class Document:
+    """A window of rendered messages with metadata, ready for a vector store"""
+
+    text: str
+    start_ts: Optional[datetime]
+    end_ts: Optional[datetime]
+    participants: List[str]
+    source: Optional[str]
+    start_id: Optional[int]
+    end_id: Optional[int]
+    message_count: int
+
+    @property
+    def platform(self) -> Optional[str]:
+        """Platform of the messages, None if they come from several platforms"""
+        ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def __len__(self) -> int: ...
+    def __repr__(self) -> str: ...
+
+class Session:
+    """Messages without a pause longer than the session gap, with summary stats"""
+
+    messages: List[Message]
+    start_ts: Optional[datetime]
+    end_ts: Optional[datetime]
+    participants: Dict[str, int]
+    """Messages per sender"""
+
+    @property
+    def duration(self) -> Optional[timedelta]: ...
+    @property
+    def message_count(self) -> int: ...
+    @property
+    def participant_count(self) -> int: ...
+    def to_dict(self) -> Dict[str, Any]:
+        """Summary stats (without the messages)"""
+        ...
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[Message]: ...
+    def __repr__(self) -> str: ...
+
+class Redactor:
+    """
+    PII redactor for messages sent to third-party models
+
+    Built-in detectors: "url" (links with credentials or tokens), "email", "card"
+    (Luhn-checked), "iban" (checksum-checked), "phone" and "mention" (@name).
+    Strategies: "placeholder" (<PHONE_1>, the same value keeps the same
+    placeholder across messages and calls), "mask" (asterisks) or "hash"
+    (<PHONE:1a2b3c4d5e6f>, salted SHA-256). Without a salt every Redactor draws
+    a random one, so hashes only match within one Redactor; pass the same
+    secret salt to get stable hashes across runs.
+    """
+
+    def __init__(
+        self,
+        detectors: Optional[List[str]] = None,
+        patterns: Optional[Dict[str, str]] = None,
+        strategy: str = "placeholder",
+        salt: Optional[str] = None,
+        senders: bool = False,
+    ) -> None:
+        """
+        Args:
+            detectors: Built-in detectors to use (default: all)
+            patterns: Custom detectors, placeholder label -> regex; they take
+                precedence over the built-in ones
+            strategy: "placeholder", "mask" or "hash"
+            salt: Secret salt for the "hash" strategy, must not be empty
+                (default: random per Redactor)
+            senders: Redact sender names and conversation titles too (e.g. phone
+                numbers in WhatsApp)
+        """
+        ...
+    @property
+    def mapping(self) -> Dict[str, str]:
+        """Original value of every placeholder handed out so far"""
+        ...
+    def redact_text(self, text: str) -> str: ...
+    def redact(self, messages: List[Message]) -> List[Message]:
+        """Redacted copies of the messages"""
+        ...
+    def restore(self, text: str) -> str:
+        """Put the original values back in place of placeholders (e.g. in a reply)"""
+        ...
+    def reset(self) -> None:
+        """Forget placeholders, numbering starts again from 1"""
+        ...
+    def __repr__(self) -> str: ...
+
+class ChatpackError(Exception):
+    """Base class for all chatpack errors"""
+
+    path: Optional[str]
+    line: Optional[int]
+    byte_offset: Optional[int]
+    platform: Optional[str]
+
+class ParseError(ChatpackError, ValueError):
+    """The export content is malformed"""
+
+class InvalidDateError(ChatpackError, ValueError):
+    """A date filter could not be parsed"""
+
+class UnsupportedFormatError(ChatpackError, ValueError):
+    """The export or output format is not supported"""
+
+class ChatpackIOError(ChatpackError, OSError):
+    """Reading or writing a file failed"""
+
 class TelegramParser:
     """Parser for Telegram JSON exports"""
 
-    def __init__(self) -> None: ...
+    def __init__(
+        self,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        output: Optional[OutputConfig] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None: ...
     def parse(
         self,
-        path: str,
-        merge: bool = False,
+        path: Source,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_str(
+        self,
+        content: str,
+        merge: Union[bool, MergeConfig] = False,
         min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_bytes(
+        self,
+        data: bytes,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
         date_from: Optional[str] = None,
         date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
     ) -> List[Message]: ...
-    def parse_str(self, content: str) -> List[Message]: ...
+    def render(self, messages: List[Message], format: str = "csv") -> str: ...
+    def write(
+        self, messages: List[Message], path: str, format: str = "csv"
+    ) -> None: ...
 
 class WhatsAppParser:
     """Parser for WhatsApp TXT exports"""
 
-    def __init__(self) -> None: ...
+    def __init__(
+        self,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        output: Optional[OutputConfig] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None: ...
     def parse(
         self,
-        path: str,
-        merge: bool = False,
+        path: Source,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_str(
+        self,
+        content: str,
+        merge: Union[bool, MergeConfig] = False,
         min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
         date_from: Optional[str] = None,
         date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
     ) -> List[Message]: ...
-    def parse_str(self, content: str) -> List[Message]: ...
+    def parse_bytes(
+        self,
+        data: bytes,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def render(self, messages: List[Message], format: str = "csv") -> str: ...
+    def write(
+        self, messages: List[Message], path: str, format: str = "csv"
+    ) -> None: ...
 
 class InstagramParser:
     """Parser for Instagram JSON exports"""
 
-    def __init__(self) -> None: ...
+    def __init__(
+        self,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        output: Optional[OutputConfig] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None: ...
     def parse(
         self,
-        path: str,
-        merge: bool = False,
+        path: Source,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_str(
+        self,
+        content: str,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_bytes(
+        self,
+        data: bytes,
+        merge: Union[bool, MergeConfig] = False,
         min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
         date_from: Optional[str] = None,
         date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
     ) -> List[Message]: ...
-    def parse_str(self, content: str) -> List[Message]: ...
+    def render(self, messages: List[Message], format: str = "csv") -> str: ...
+    def write(
+        self, messages: List[Message], path: str, format: str = "csv"
+    ) -> None: ...
 
 class DiscordParser:
     """Parser for Discord exports"""
 
-    def __init__(self) -> None: ...
+    def __init__(
+        self,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        output: Optional[OutputConfig] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None: ...
     def parse(
         self,
-        path: str,
-        merge: bool = False,
+        path: Source,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def parse_str(
+        self,
+        content: str,
+        merge: Union[bool, MergeConfig] = False,
         min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
         date_from: Optional[str] = None,
         date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
     ) -> List[Message]: ...
-    def parse_str(self, content: str) -> List[Message]: ...
+    def parse_bytes(
+        self,
+        data: bytes,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Optional[Union[bool, Redactor]] = None,
+    ) -> List[Message]: ...
+    def render(self, messages: List[Message], format: str = "csv") -> str: ...
+    def write(
+        self, messages: List[Message], path: str, format: str = "csv"
+    ) -> None: ...
 
 class TelegramStreamParser:
     """Streaming parser for large Telegram exports"""
 
-    def __init__(self, path: str) -> None: ...
+    def __init__(
+        self,
+        path: str,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        sender: Optional[str] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None:
+        """
+        Keyword arguments override the matching FilterConfig fields.
+        With merge, only the current run of same-sender messages is buffered.
+        Redaction runs last, filters match the original senders.
+        """
+        ...
+    def iter_batches(
+        self, batch_size: int = 10_000, arrow: Optional[bool] = None
+    ) -> Iterator[Union[List[Message], Any]]:
+        """
+        Iterate in lists of up to batch_size messages.
+
+        With arrow=True (the default when pyarrow is installed) yields
+        pyarrow.RecordBatch objects with the columns sender, content, timestamp,
+        platform, id, reply_to, edited and conversation instead.
+        """
+        ...
+    def iter_sessions(self, gap: timedelta = timedelta(hours=2)) -> Iterator[Session]:
+        """Iterate over conversation sessions split by pauses longer than gap"""
+        ...
     def __iter__(self) -> Iterator[Message]: ...
+    def __aiter__(self) -> AsyncIterator[Message]:
+        """Iterate from asyncio code, reading ahead in a worker thread"""
+        ...
 
 class WhatsAppStreamParser:
     """Streaming parser for large WhatsApp exports"""
 
-    def __init__(self, path: str) -> None: ...
+    def __init__(
+        self,
+        path: str,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        sender: Optional[str] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None:
+        """
+        Keyword arguments override the matching FilterConfig fields.
+        With merge, only the current run of same-sender messages is buffered.
+        Redaction runs last, filters match the original senders.
+        """
+        ...
+    def iter_batches(
+        self, batch_size: int = 10_000, arrow: Optional[bool] = None
+    ) -> Iterator[Union[List[Message], Any]]:
+        """
+        Iterate in lists of up to batch_size messages.
+
+        With arrow=True (the default when pyarrow is installed) yields
+        pyarrow.RecordBatch objects with the columns sender, content, timestamp,
+        platform, id, reply_to, edited and conversation instead.
+        """
+        ...
+    def iter_sessions(self, gap: timedelta = timedelta(hours=2)) -> Iterator[Session]:
+        """Iterate over conversation sessions split by pauses longer than gap"""
+        ...
     def __iter__(self) -> Iterator[Message]: ...
+    def __aiter__(self) -> AsyncIterator[Message]:
+        """Iterate from asyncio code, reading ahead in a worker thread"""
+        ...
 
 class InstagramStreamParser:
     """Streaming parser for large Instagram exports"""
 
-    def __init__(self, path: str) -> None: ...
+    def __init__(
+        self,
+        path: str,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        sender: Optional[str] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None:
+        """
+        Keyword arguments override the matching FilterConfig fields.
+        With merge, only the current run of same-sender messages is buffered.
+        Redaction runs last, filters match the original senders.
+        """
+        ...
+    def iter_batches(
+        self, batch_size: int = 10_000, arrow: Optional[bool] = None
+    ) -> Iterator[Union[List[Message], Any]]:
+        """
+        Iterate in lists of up to batch_size messages.
+
+        With arrow=True (the default when pyarrow is installed) yields
+        pyarrow.RecordBatch objects with the columns sender, content, timestamp,
+        platform, id, reply_to, edited and conversation instead.
+        """
+        ...
+    def iter_sessions(self, gap: timedelta = timedelta(hours=2)) -> Iterator[Session]:
+        """Iterate over conversation sessions split by pauses longer than gap"""
+        ...
     def __iter__(self) -> Iterator[Message]: ...
+    def __aiter__(self) -> AsyncIterator[Message]:
+        """Iterate from asyncio code, reading ahead in a worker thread"""
+        ...
 
 class DiscordStreamParser:
     """Streaming parser for large Discord exports"""
 
-    def __init__(self, path: str) -> None: ...
+    def __init__(
+        self,
+        path: str,
+        filter: Optional[FilterConfig] = None,
+        merge: Union[bool, MergeConfig] = False,
+        min_length: Optional[int] = None,
+        max_length: Optional[int] = None,
+        sender: Optional[str] = None,
+        date_from: Optional[str] = None,
+        date_to: Optional[str] = None,
+        redact: Union[bool, Redactor] = False,
+    ) -> None:
+        """
+        Keyword arguments override the matching FilterConfig fields.
+        With merge, only the current run of same-sender messages is buffered.
+        Redaction runs last, filters match the original senders.
+        """
+        ...
+    def iter_batches(
+        self, batch_size: int = 10_000, arrow: Optional[bool] = None
+    ) -> Iterator[Union[List[Message], Any]]:
+        """
+        Iterate in lists of up to batch_size messages.
+
+        With arrow=True (the default when pyarrow is installed) yields
+        pyarrow.RecordBatch objects with the columns sender, content, timestamp,
+        platform, id, reply_to, edited and conversation instead.
+        """
+        ...
+    def iter_sessions(self, gap: timedelta = timedelta(hours=2)) -> Iterator[Session]:
+        """Iterate over conversation sessions split by pauses longer than gap"""
+        ...
     def __iter__(self) -> Iterator[Message]: ...
+    def __aiter__(self) -> AsyncIterator[Message]:
+        """Iterate from asyncio code, reading ahead in a worker thread"""
+        ...
 
 def parse_telegram(
-    path: str,
-    merge: bool = False,
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
     min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
 ) -> List[Message]:
     """
     Parse Telegram JSON export
 
     Args:
-        path: Path to the Telegram export file (result.json)
-        merge: Merge consecutive messages from the same sender
+        path: Path to the Telegram export file (result.json) or a zipped
+            export, raw bytes or a file-like object
+        merge: Merge consecutive messages from the same sender (True or a MergeConfig)
         min_length: Minimum message length to include
+        max_length: Maximum message length to include
         date_from: Filter messages from this date (ISO format)
         date_to: Filter messages until this date (ISO format)
+        redact: Redact PII from the parsed messages (True or a Redactor)
 
     Returns:
         List of parsed messages
@@ -160,21 +625,26 @@ def parse_telegram(
     ...
 
 def parse_whatsapp(
-    path: str,
-    merge: bool = False,
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
     min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
 ) -> List[Message]:
     """
     Parse WhatsApp TXT export
 
     Args:
-        path: Path to the WhatsApp export file (chat.txt)
-        merge: Merge consecutive messages from the same sender
+        path: Path to the WhatsApp export file (chat.txt) or the .zip made by
+            "Export chat", raw bytes or a file-like object
+        merge: Merge consecutive messages from the same sender (True or a MergeConfig)
         min_length: Minimum message length to include
+        max_length: Maximum message length to include
         date_from: Filter messages from this date (ISO format)
         date_to: Filter messages until this date (ISO format)
+        redact: Redact PII from the parsed messages (True or a Redactor)
 
     Returns:
         List of parsed messages
@@ -182,21 +652,28 @@ def parse_whatsapp(
     ...
 
 def parse_instagram(
-    path: str,
-    merge: bool = False,
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
     min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
 ) -> List[Message]:
     """
     Parse Instagram JSON export (GDPR dump)
 
     Args:
-        path: Path to the Instagram export file (messages.json)
-        merge: Merge consecutive messages from the same sender
+        path: Path to the Instagram export file (message_1.json),
+            a conversation directory with message_N.json parts,
+            the inbox/ root, a zipped data download,
+            raw bytes or a file-like object
+        merge: Merge consecutive messages from the same sender (True or a MergeConfig)
         min_length: Minimum message length to include
+        max_length: Maximum message length to include
         date_from: Filter messages from this date (ISO format)
         date_to: Filter messages until this date (ISO format)
+        redact: Redact PII from the parsed messages (True or a Redactor)
 
     Returns:
         List of parsed messages
@@ -204,36 +681,340 @@ def parse_instagram(
     ...
 
 def parse_discord(
-    path: str,
-    merge: bool = False,
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
     min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
 ) -> List[Message]:
     """
     Parse Discord export (JSON/CSV/TXT from DiscordChatExporter)
 
     Args:
-        path: Path to the Discord export file
-        merge: Merge consecutive messages from the same sender
+        path: Path to the Discord export file or a zip of exports,
+            raw bytes or a file-like object
+        merge: Merge consecutive messages from the same sender (True or a MergeConfig)
         min_length: Minimum message length to include
+        max_length: Maximum message length to include
         date_from: Filter messages from this date (ISO format)
         date_to: Filter messages until this date (ISO format)
+        redact: Redact PII from the parsed messages (True or a Redactor)
 
     Returns:
         List of parsed messages
     """
     ...
 
+def detect_platform(path: Source) -> Tuple[Optional[Platform], float]:
+    """
+    Detect the source platform of an export by sniffing its content
+
+    Args:
+        path: Path to the export file or zipped export, raw bytes
+            or a file-like object
+
+    Returns:
+        Tuple of (platform or None if unknown, confidence between 0 and 1)
+    """
+    ...
+
+def parse_auto(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> List[Message]:
+    """
+    Parse an export of unknown platform, detected with detect_platform()
+
+    Takes the same arguments as parse_telegram() and friends.
+    Raises UnsupportedFormatError if the platform can't be detected.
+    """
+    ...
+
+def async_parse_telegram(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Coroutine[Any, Any, List[Message]]:
+    """Coroutine version of parse_telegram(), the parse runs off the event loop thread"""
+    ...
+
+def async_parse_whatsapp(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Coroutine[Any, Any, List[Message]]:
+    """Coroutine version of parse_whatsapp(), the parse runs off the event loop thread"""
+    ...
+
+def async_parse_instagram(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Coroutine[Any, Any, List[Message]]:
+    """Coroutine version of parse_instagram(), the parse runs off the event loop thread"""
+    ...
+
+def async_parse_discord(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Coroutine[Any, Any, List[Message]]:
+    """Coroutine version of parse_discord(), the parse runs off the event loop thread"""
+    ...
+
+def async_parse_auto(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Coroutine[Any, Any, List[Message]]:
+    """Coroutine version of parse_auto(), the parse runs off the event loop thread"""
+    ...
+
+def parse_many(
+    paths: List[Union[str, "os.PathLike[str]"]],
+    platform: Optional[Union[str, Platform]] = None,
+    workers: Optional[int] = None,
+    by_path: bool = False,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Tuple[Union[List[Message], Dict[str, List[Message]]], Dict[str, ChatpackError]]:
+    """
+    Parse many exports in parallel on a Rust thread pool
+
+    Args:
+        paths: Export files to parse
+        platform: Platform of all files, detected per file if None
+        workers: Number of worker threads (defaults to the CPU count)
+        by_path: Return a dict of messages keyed by path instead of one list
+
+    The remaining arguments are the same as for parse_telegram() and friends.
+
+    Returns:
+        Tuple of (messages, errors). Messages are one list in chronological
+        order, or a dict keyed by path with by_path=True. Errors map the paths
+        that failed to their exceptions; the other files are still parsed.
+    """
+    ...
+
+def referenced_media(path: Source, messages: List[Message]) -> Dict[str, List[int]]:
+    """
+    Find the media files of a zipped export that messages refer to
+
+    Args:
+        path: The zipped export, raw bytes or a file-like object
+        messages: Messages parsed from it
+
+    Returns:
+        Dict mapping archive entries (e.g. "IMG-0001.jpg") to the indices
+        of the messages that mention their file name
+    """
+    ...
+
+def count_tokens(
+    messages: List[Message],
+    format: str = "csv",
+    tokenizer: TokenizerLike = "cl100k",
+    config: Optional[OutputConfig] = None,
+) -> int:
+    """
+    Count the tokens of messages rendered as csv, json or jsonl
+
+    Args:
+        messages: Messages to render
+        format: Output format, as in to_csv_string() and friends
+        tokenizer: "cl100k" (GPT-4), "o200k" (GPT-4o), "chars" (~4 chars per
+            token) or a custom tokenizer
+        config: Output configuration used for rendering
+    """
+    ...
+
+def chunk_by_tokens(
+    messages: List[Message],
+    max_tokens: int,
+    overlap_messages: int = 0,
+    format: str = "csv",
+    tokenizer: TokenizerLike = "cl100k",
+    config: Optional[OutputConfig] = None,
+) -> List[List[Message]]:
+    """
+    Split messages into prompt-sized windows on message boundaries
+
+    Every window renders to at most max_tokens tokens, except a single message
+    that is larger on its own. Each window starts with the last
+    overlap_messages messages of the previous one.
+    """
+    ...
+
+def to_documents(
+    messages: List[Message],
+    window: int = 20,
+    stride: Optional[int] = None,
+    idle_gap: timedelta = timedelta(hours=2),
+    format: str = "csv",
+    config: Optional[OutputConfig] = None,
+    source: Optional[Union[str, "os.PathLike[str]"]] = None,
+) -> List[Document]:
+    """
+    Split messages into documents for retrieval (RAG)
+
+    Args:
+        messages: Messages in chronological order
+        window: Maximum number of messages per document
+        stride: Messages between document starts (default: window, no overlap)
+        idle_gap: A longer pause between messages starts a new session;
+            documents never span one and full windows end at their longest pause
+        format: Format of Document.text, as in to_csv_string() and friends
+        config: Output configuration used for rendering
+        source: Export path recorded on every document
+    """
+    ...
+
+def split_sessions(
+    messages: List[Message], gap: timedelta = timedelta(hours=2)
+) -> List[Session]:
+    """
+    Group messages into conversation sessions
+
+    A pause longer than gap between two messages starts a new session; messages
+    without a timestamp stay in the current one. Unlike merge_consecutive this
+    ignores sender changes.
+    """
+    ...
+
+def redact(
+    messages: List[Message], redactor: Optional[Redactor] = None
+) -> List[Message]:
+    """
+    Redacted copies of the messages
+
+    Uses a default Redactor() (all detectors, placeholders) unless one is given;
+    pass the same Redactor to keep placeholders consistent across calls.
+    """
+    ...
+
+def to_arrow(messages: List[Message]) -> Any:
+    """
+    Convert messages to a pyarrow.Table through the Arrow C data interface
+
+    Columns: sender, content, timestamp (timestamp[ms, tz=UTC]), platform,
+    id, reply_to, edited and conversation. Requires pyarrow
+    (pip install chatpack[arrow]).
+    """
+    ...
+
+def to_pandas(messages: List[Message]) -> Any:
+    """Convert messages to a pandas.DataFrame (through to_arrow)"""
+    ...
+
+def to_polars(messages: List[Message]) -> Any:
+    """Convert messages to a polars.DataFrame (through to_arrow)"""
+    ...
+
+def parse_telegram_to_arrow(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Any:
+    """Like parse_telegram(), but returns a pyarrow.Table without creating Message objects"""
+    ...
+
+def parse_whatsapp_to_arrow(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Any:
+    """Like parse_whatsapp(), but returns a pyarrow.Table without creating Message objects"""
+    ...
+
+def parse_instagram_to_arrow(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Any:
+    """Like parse_instagram(), but returns a pyarrow.Table without creating Message objects"""
+    ...
+
+def parse_discord_to_arrow(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Any:
+    """Like parse_discord(), but returns a pyarrow.Table without creating Message objects"""
+    ...
+
+def parse_auto_to_arrow(
+    path: Source,
+    merge: Union[bool, MergeConfig] = False,
+    min_length: Optional[int] = None,
+    max_length: Optional[int] = None,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    redact: Union[bool, Redactor] = False,
+) -> Any:
+    """Like parse_auto(), but returns a pyarrow.Table without creating Message objects"""
+    ...
+
 def merge_consecutive(
-    messages: List[Message], time_threshold: int = 300
+    messages: List[Message],
+    time_threshold: Optional[int] = 300,
+    max_merged_length: Optional[int] = None,
+    separator: str = "\n",
 ) -> List[Message]:
     """
     Merge consecutive messages from the same sender
 
     Args:
         messages: List of messages to merge
-        time_threshold: Maximum time gap in seconds between messages
+        time_threshold: Maximum time gap in seconds between messages (None = no limit)
+        max_merged_length: Maximum length of a merged message in characters
+        separator: Text inserted between merged messages
 
     Returns:
         List of merged messages
@@ -253,6 +1034,97 @@ def apply_filters(messages: List[Message], config: FilterConfig) -> List[Message
     """
     ...
 
+def to_csv_string(
+    messages: List[Message], config: Optional[OutputConfig] = None
+) -> str:
+    """
+    Render messages as CSV
+
+    Args:
+        messages: List of messages to render
+        config: Output configuration (timestamps on, platform off by default)
+
+    Returns:
+        CSV text with a header row
+    """
+    ...
+
+def to_json_string(
+    messages: List[Message], config: Optional[OutputConfig] = None
+) -> str:
+    """Render messages as a pretty-printed JSON array"""
+    ...
+
+def to_jsonl_string(
+    messages: List[Message], config: Optional[OutputConfig] = None
+) -> str:
+    """Render messages as JSONL (one JSON object per line)"""
+    ...
+
+def write_csv(
+    messages: List[Message], path: str, config: Optional[OutputConfig] = None
+) -> None:
+    """
+    Write messages to a CSV file
+
+    Args:
+        messages: List of messages to write
+        path: Output file path
+        config: Output configuration (timestamps on, platform off by default)
+    """
+    ...
+
+def write_json(
+    messages: List[Message], path: str, config: Optional[OutputConfig] = None
+) -> None:
+    """Write messages to a JSON file"""
+    ...
+
+def write_jsonl(
+    messages: List[Message], path: str, config: Optional[OutputConfig] = None
+) -> None:
+    """Write messages to a JSONL file"""
+    ...
+
+def write_parquet(
+    messages: List[Message],
+    path: Union[str, "os.PathLike[str]"],
+    compression: str = "zstd",
+) -> None:
+    """
+    Write messages to a Parquet file
+
+    Columns: sender, content, timestamp, platform, id, reply_to, edited, conversation.
+
+    Args:
+        messages: List of messages to write
+        path: Output file path
+        compression: zstd, snappy, gzip, lz4 or none
+    """
+    ...
+
+def write_parquet_stream(
+    stream: Union[
+        TelegramStreamParser,
+        WhatsAppStreamParser,
+        InstagramStreamParser,
+        DiscordStreamParser,
+    ],
+    path: Union[str, "os.PathLike[str]"],
+    compression: str = "zstd",
+    row_group_size: int = 100_000,
+) -> int:
+    """
+    Write everything a stream parser yields to a Parquet file,
+    one row group per row_group_size messages, with flat memory use
+
+    Returns:
+        Number of messages written
+    """
+    ...
+
 PyMessage = Message
+PyPlatform = Platform
 PyFilterConfig = FilterConfig
+PyMergeConfig = MergeConfig
 PyOutputConfig = OutputConfig
@@ -0,0 +1,369 @@
+// Zipped exports: WhatsApp "Export chat" archives (_chat.txt + media),
+// Telegram and Instagram data downloads
+//
+// Chat files are found by sniffing the first bytes of the entries. Line-based chats
+// (WhatsApp TXT, Discord JSONL) are parsed while they are decompressed; JSON exports need
+// the whole text. Nothing is extracted to disk, and at most MAX_DECOMPRESSED_SIZE bytes
+// are decompressed per archive, so a zip bomb fails with ChatpackIOError
+
+use crate::detect::{self, Detection};
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::instagram;
+use crate::lines;
+use crate::parsers::{Parsed, Pipeline};
+use crate::source::{read_head, utf8_error};
+use crate::types::{PyMessage, PyPlatform};
+use aho_corasick::{AhoCorasick, MatchKind};
+use chatpack::parser::Parser;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Extensions of entries that may hold a chat
+const CHAT_EXTENSIONS: &[&str] = &["json", "jsonl", "txt", "csv"];
+
+/// Extensions of the non-media files that come with exports (Telegram HTML export etc.)
+const TEXT_EXTENSIONS: &[&str] = &["json", "jsonl", "txt", "csv", "html", "css", "js"];
+
+/// Total bytes of chat entries decompressed from one archive
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+/// Does the content start with a zip signature
+pub fn is_zip(head: &[u8]) -> bool {
+    head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06")
+}
+
+/// Is the file a zip archive
+pub fn is_zip_file(path: &Path) -> bool {
+    path.is_file() && read_head(path, 4).is_ok_and(|head| is_zip(&head))
+}
+
+pub fn open_path(path: &Path) -> Result<ChatArchive<BufReader<File>>, ErrorInfo> {
+    let file = File::open(path).map_err(|e| ErrorInfo::io(&e, path))?;
+    ChatArchive::new(BufReader::new(file), Some(path))
+}
+
+pub fn open_bytes(data: &[u8]) -> Result<ChatArchive<Cursor<&[u8]>>, ErrorInfo> {
+    ChatArchive::new(Cursor::new(data), None)
+}
+
+struct Entry {
+    index: usize,
+    name: String,
+}
+
+impl Entry {
+    fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    fn has_extension(&self, extensions: &[&str]) -> bool {
+        Path::new(&self.name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+    }
+}
+
+pub struct ChatArchive<R> {
+    archive: ZipArchive<R>,
+    /// Archive path, for error context
+    path: Option<PathBuf>,
+    /// Bytes left of MAX_DECOMPRESSED_SIZE
+    budget: u64,
+}
+
+impl<R: Read + Seek> ChatArchive<R> {
+    fn new(reader: R, path: Option<&Path>) -> Result<Self, ErrorInfo> {
+        Ok(ChatArchive {
+            archive: ZipArchive::new(reader).map_err(|e| zip_error(e, path))?,
+            path: path.map(Path::to_path_buf),
+            budget: MAX_DECOMPRESSED_SIZE,
+        })
+    }
+
+    // Helper: "export.zip/folder/_chat.txt", so errors point into the archive
+    fn entry_path(&self, entry: &Entry) -> PathBuf {
+        match &self.path {
+            Some(path) => path.join(&entry.name),
+            None => PathBuf::from(&entry.name),
+        }
+    }
+
+    /// File entries sorted by name, without macOS metadata
+    fn files(&mut self) -> Result<Vec<Entry>, ErrorInfo> {
+        let mut entries = Vec::new();
+        for index in 0..self.archive.len() {
+            let file = self
+                .archive
+                .by_index(index)
+                .map_err(|e| zip_error(e, self.path.as_deref()))?;
+            let entry = Entry {
+                index,
+                name: file.name().to_string(),
+            };
+
+            if file.is_file()
+                && !entry.name.starts_with("__MACOSX/")
+                && !entry.file_name().starts_with("._")
+            {
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// Decompress an entry, at most `limit` bytes of it.
+    /// Full reads are charged against the archive's decompressed size budget
+    fn read(&mut self, entry: &Entry, limit: Option<usize>) -> Result<Vec<u8>, ErrorInfo> {
+        let path = self.entry_path(entry);
+        let file = self
+            .archive
+            .by_index(entry.index)
+            .map_err(|e| zip_error(e, Some(&path)))?;
+
+        // The declared size may lie, so the read itself is capped as well
+        let cap = match limit {
+            Some(limit) => limit as u64,
+            None if file.size() > self.budget => return Err(size_limit_error(&path)),
+            None => self.budget + 1,
+        };
+
+        let mut data = Vec::new();
+        file.take(cap)
+            .read_to_end(&mut data)
+            .map_err(|e| ErrorInfo::io(&e, &path))?;
+
+        if limit.is_none() {
+            if data.len() as u64 > self.budget {
+                return Err(size_limit_error(&path));
+            }
+            self.budget -= data.len() as u64;
+        }
+        Ok(data)
+    }
+
+    fn read_string(&mut self, entry: &Entry) -> Result<(PathBuf, String), ErrorInfo> {
+        let path = self.entry_path(entry);
+        let content = String::from_utf8(self.read(entry, None)?)
+            .map_err(|e| utf8_error(e.utf8_error()).with_path(&path))?;
+        Ok((path, content))
+    }
+
+    /// Chat entries with their detections, only the first bytes of each are decompressed
+    fn chats(&mut self) -> Result<Vec<(Entry, Detection)>, ErrorInfo> {
+        let mut chats = Vec::new();
+        for entry in self.files()? {
+            if !entry.has_extension(CHAT_EXTENSIONS) {
+                continue;
+            }
+
+            let head = self.read(&entry, Some(detect::SNIFF_BYTES))?;
+            if let Some(detection) = detect::detect(&head) {
+                chats.push((entry, detection));
+            }
+        }
+        Ok(chats)
+    }
+
+    /// Platform of the chats in the archive
+    pub fn detect(&mut self) -> Result<Option<Detection>, ErrorInfo> {
+        Ok(self
+            .chats()?
+            .into_iter()
+            .map(|(_, detection)| detection)
+            .reduce(|best, d| {
+                if d.confidence > best.confidence {
+                    d
+                } else {
+                    best
+                }
+            }))
+    }
+
+    /// Parse every chat of the parser's platform, running the pipeline per chat
+    pub fn parse(&mut self, parser: &dyn Parser, pipeline: &Pipeline) -> Result<Parsed, ErrorInfo> {
+        let platform = PyPlatform::try_from(parser.platform())?;
+        let entries: Vec<Entry> = self
+            .chats()?
+            .into_iter()
+            .filter(|(_, detection)| detection.platform == platform)
+            .map(|(entry, _)| entry)
+            .collect();
+
+        if entries.is_empty() {
+            let info = ErrorInfo::new(
+                ErrorKind::UnsupportedFormat,
+                format!("No {} chat found in the archive", platform.as_str()),
+            )
+            .with_platform(platform);
+            return Err(match &self.path {
+                Some(path) => info.with_path(path),
+                None => info,
+            });
+        }
+
+        if platform == PyPlatform::Instagram {
+            return self.parse_instagram(parser, pipeline, entries);
+        }
+
+        let mut result = Parsed::new(platform);
+        for entry in entries {
+            let head = self.read(&entry, Some(detect::SNIFF_BYTES))?;
+            if lines::supports(platform, &head) {
+                let messages = self.parse_lines(&entry, platform, pipeline)?;
+                result.push(messages, None);
+                continue;
+            }
+
+            let (path, content) = self.read_string(&entry)?;
+            let messages = parser
+                .parse_str(&content)
+                .map_err(|e| ErrorInfo::from(e).with_path(&path).with_platform(platform))?;
+            result.push(pipeline.run(messages), None);
+        }
+
+        Ok(result)
+    }
+
+    // Parse a line-based chat while it is decompressed, charging the bytes read
+    // against the size budget
+    fn parse_lines(
+        &mut self,
+        entry: &Entry,
+        platform: PyPlatform,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<chatpack::Message>, ErrorInfo> {
+        let path = self.entry_path(entry);
+        let file = self
+            .archive
+            .by_index(entry.index)
+            .map_err(|e| zip_error(e, Some(&path)))?;
+        if file.size() > self.budget {
+            return Err(size_limit_error(&path));
+        }
+
+        let cap = self.budget + 1;
+        let mut reader = BufReader::new(file.take(cap));
+        let messages =
+            lines::messages(platform, &mut reader).and_then(|messages| pipeline.run_each(messages));
+
+        // Past the cap the text is cut short, which is no parse error of its own
+        let read = cap - reader.get_ref().limit();
+        if read > self.budget {
+            return Err(size_limit_error(&path));
+        }
+        self.budget -= read;
+
+        messages.map_err(|e| e.with_path(&path).with_platform(platform))
+    }
+
+    // Instagram conversations are split into message_N.json parts per directory
+    fn parse_instagram(
+        &mut self,
+        parser: &dyn Parser,
+        pipeline: &Pipeline,
+        entries: Vec<Entry>,
+    ) -> Result<Parsed, ErrorInfo> {
+        let mut conversations: BTreeMap<String, Vec<(u32, Entry)>> = BTreeMap::new();
+        for entry in entries {
+            let Some(number) = instagram::part_number(entry.file_name()) else {
+                continue;
+            };
+            let dir = entry
+                .name
+                .rsplit_once('/')
+                .map_or("", |(dir, _)| dir)
+                .to_string();
+            conversations.entry(dir).or_default().push((number, entry));
+        }
+
+        let mut result = Parsed::new(PyPlatform::Instagram);
+        for (_, mut parts) in conversations {
+            parts.sort_by_key(|(number, _)| std::cmp::Reverse(*number));
+            let parts = parts.iter().map(|(_, entry)| self.read_string(entry));
+            let (messages, title) = instagram::parse_conversation(parser, pipeline, parts)?;
+            result.push(messages, title);
+        }
+
+        Ok(result)
+    }
+
+    /// Media entries referenced by the messages, mapped to the indices of the
+    /// messages that mention their file name
+    pub fn referenced_media(
+        &mut self,
+        messages: &[PyMessage],
+    ) -> Result<BTreeMap<String, Vec<usize>>, ErrorInfo> {
+        let media: Vec<Entry> = self
+            .files()?
+            .into_iter()
+            .filter(|entry| !entry.has_extension(TEXT_EXTENSIONS))
+            .collect();
+
+        let mut referenced: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        if media.is_empty() {
+            return Ok(referenced);
+        }
+
+        let names = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(media.iter().map(Entry::file_name))
+            .map_err(|e| ErrorInfo::new(ErrorKind::Base, e.to_string()))?;
+
+        for (i, msg) in messages.iter().enumerate() {
+            for found in names.find_iter(&msg.content) {
+                // "1.jpg" must not match inside "photo_1.jpg"
+                let preceding = msg.content[..found.start()].chars().next_back();
+                if preceding.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+                    continue;
+                }
+
+                let file_name = media[found.pattern().as_usize()].file_name();
+                for entry in media.iter().filter(|e| e.file_name() == file_name) {
+                    let indices = referenced.entry(entry.name.clone()).or_default();
+                    if indices.last() != Some(&i) {
+                        indices.push(i);
+                    }
+                }
+            }
+        }
+
+        Ok(referenced)
+    }
+}
+
+// Helper: map zip errors, a broken archive is a parse error
+fn size_limit_error(path: &Path) -> ErrorInfo {
+    ErrorInfo::new(
+        ErrorKind::Io,
+        format!(
+            "Archive exceeds the decompressed size limit of {} MiB",
+            MAX_DECOMPRESSED_SIZE >> 20
+        ),
+    )
+    .with_path(path)
+}
+
+fn zip_error(err: ZipError, path: Option<&Path>) -> ErrorInfo {
+    let info = match err {
+        ZipError::Io(e) => match path {
+            Some(path) => return ErrorInfo::io(&e, path),
+            None => ErrorInfo::new(ErrorKind::Io, e.to_string()),
+        },
+        ZipError::UnsupportedArchive(_) | ZipError::InvalidPassword => {
+            ErrorInfo::new(ErrorKind::UnsupportedFormat, err.to_string())
+        }
+        _ => ErrorInfo::new(ErrorKind::Parse, err.to_string()),
+    };
+
+    match path {
+        Some(path) => info.with_path(path),
+        None => info,
+    }
+}
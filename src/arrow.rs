@@ -0,0 +1,158 @@
+// Arrow output: record batches are built in Rust and handed to pyarrow through
+// the Arrow C data interface (pyarrow is imported lazily, it is an optional dependency)
+//
+// Columns: sender, content, timestamp, platform, id, reply_to, edited, conversation
+// Timestamps are timestamp[ms, tz=UTC]
+
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::parsers::Parsed;
+use crate::types::{PyMessage, PyPlatform};
+use ::arrow::array::{ArrayRef, StringArray, TimestampMillisecondArray, UInt64Array};
+use ::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use ::arrow::pyarrow::{IntoPyArrow, ToPyArrow};
+use ::arrow::record_batch::{RecordBatch, RecordBatchIterator, RecordBatchReader};
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+use std::sync::{Arc, OnceLock};
+
+/// Is pyarrow importable
+pub fn is_available(py: Python<'_>) -> bool {
+    py.import_bound("pyarrow").is_ok()
+}
+
+pub fn import(py: Python<'_>) -> PyResult<Bound<'_, PyModule>> {
+    py.import_bound("pyarrow")
+}
+
+/// Schema shared by every batch
+pub fn schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+
+    SCHEMA
+        .get_or_init(|| {
+            let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+            Arc::new(Schema::new(vec![
+                Field::new("sender", DataType::Utf8, false),
+                Field::new("content", DataType::Utf8, false),
+                Field::new("timestamp", timestamp.clone(), true),
+                Field::new("platform", DataType::Utf8, true),
+                Field::new("id", DataType::UInt64, true),
+                Field::new("reply_to", DataType::UInt64, true),
+                Field::new("edited", timestamp, true),
+                Field::new("conversation", DataType::Utf8, true),
+            ]))
+        })
+        .clone()
+}
+
+/// A message as a table row, borrowed from either message type
+#[derive(Clone, Copy)]
+struct Row<'a> {
+    sender: &'a str,
+    content: &'a str,
+    timestamp: Option<DateTime<Utc>>,
+    platform: Option<PyPlatform>,
+    id: Option<u64>,
+    reply_to: Option<u64>,
+    edited: Option<DateTime<Utc>>,
+    conversation: Option<&'a str>,
+}
+
+// Helper: the columns of the rows, in schema order
+fn columns<'a>(rows: impl Iterator<Item = Row<'a>> + Clone) -> Result<RecordBatch, ErrorInfo> {
+    let timestamps = |get: fn(&Row) -> Option<DateTime<Utc>>| {
+        TimestampMillisecondArray::from_iter(
+            rows.clone()
+                .map(|r| get(&r).map(|ts| ts.timestamp_millis())),
+        )
+        .with_timezone("UTC")
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.clone().map(|r| r.sender),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.clone().map(|r| r.content),
+        )),
+        Arc::new(timestamps(|r| r.timestamp)),
+        Arc::new(StringArray::from_iter(
+            rows.clone().map(|r| r.platform.map(|p| p.as_str())),
+        )),
+        Arc::new(UInt64Array::from_iter(rows.clone().map(|r| r.id))),
+        Arc::new(UInt64Array::from_iter(rows.clone().map(|r| r.reply_to))),
+        Arc::new(timestamps(|r| r.edited)),
+        Arc::new(StringArray::from_iter(rows.map(|r| r.conversation))),
+    ];
+
+    RecordBatch::try_new(schema(), columns)
+        .map_err(|e| ErrorInfo::new(ErrorKind::Base, e.to_string()))
+}
+
+/// Build a record batch, needs no GIL
+pub fn build(messages: &[PyMessage]) -> Result<RecordBatch, ErrorInfo> {
+    columns(messages.iter().map(|m| Row {
+        sender: &m.sender,
+        content: &m.content,
+        timestamp: m.timestamp,
+        platform: m.platform,
+        id: m.id,
+        reply_to: m.reply_to,
+        edited: m.edited,
+        conversation: m.conversation.as_deref(),
+    }))
+}
+
+/// Build a record batch straight from parsed upstream messages, needs no GIL
+pub fn build_parsed(parsed: &Parsed) -> Result<RecordBatch, ErrorInfo> {
+    let rows = parsed.messages.iter().zip(parsed.titles());
+    columns(rows.map(|(m, conversation)| Row {
+        sender: &m.sender,
+        content: &m.content,
+        timestamp: m.timestamp,
+        platform: Some(parsed.platform),
+        id: m.id,
+        reply_to: m.reply_to,
+        edited: m.edited,
+        conversation,
+    }))
+}
+
+/// Convert messages to a pyarrow.RecordBatch
+pub fn record_batch<'py>(py: Python<'py>, messages: &[PyMessage]) -> PyResult<Bound<'py, PyAny>> {
+    let batch = py.allow_threads(|| build(messages))?;
+    Ok(batch.to_pyarrow(py)?.into_bound(py))
+}
+
+// Helper: hand a batch to pyarrow as a Table, exported as an Arrow stream
+fn into_table(py: Python<'_>, batch: RecordBatch) -> PyResult<PyObject> {
+    let reader: Box<dyn RecordBatchReader + Send> =
+        Box::new(RecordBatchIterator::new([Ok(batch)], schema()));
+    reader.into_pyarrow(py)?.call_method0(py, "read_all")
+}
+
+/// Convert messages to a pyarrow.Table
+pub fn table(py: Python<'_>, messages: &[PyMessage]) -> PyResult<PyObject> {
+    let batch = py.allow_threads(|| build(messages))?;
+    into_table(py, batch)
+}
+
+/// Convert parsed messages to a pyarrow.Table without making Message objects
+pub fn parsed_table(py: Python<'_>, parsed: &Parsed) -> PyResult<PyObject> {
+    let batch = py.allow_threads(|| build_parsed(parsed))?;
+    into_table(py, batch)
+}
+
+/// Convert messages to a pandas.DataFrame (through Arrow)
+pub fn pandas(py: Python<'_>, messages: &[PyMessage]) -> PyResult<PyObject> {
+    table(py, messages)?.call_method0(py, "to_pandas")
+}
+
+/// Convert messages to a polars.DataFrame (through Arrow, without copying)
+pub fn polars(py: Python<'_>, messages: &[PyMessage]) -> PyResult<PyObject> {
+    let table = table(py, messages)?;
+    Ok(py
+        .import_bound("polars")?
+        .call_method1("from_arrow", (table,))?
+        .unbind())
+}
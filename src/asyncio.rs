@@ -0,0 +1,49 @@
+// asyncio integration without an async runtime: the work runs in the event loop's
+// default executor (its thread releases the GIL for the Rust part), so the loop
+// gets an asyncio.Future and the threads are joined when the loop shuts down
+
+use pyo3::prelude::*;
+
+type Work = Box<dyn FnOnce(Python<'_>) -> PyResult<PyObject> + Send>;
+
+// Callable submitted to the executor
+#[pyclass]
+struct Job {
+    work: Option<Work>,
+}
+
+#[pymethods]
+impl Job {
+    fn __call__(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        match self.work.take() {
+            Some(work) => work(py),
+            None => Ok(py.None()),
+        }
+    }
+}
+
+/// Run `work` off the event loop thread, returning an asyncio.Future of its result.
+/// Must be called from a running event loop
+pub fn spawn<F>(py: Python<'_>, work: F) -> PyResult<PyObject>
+where
+    F: FnOnce(Python<'_>) -> PyResult<PyObject> + Send + 'static,
+{
+    let job = Job {
+        work: Some(Box::new(work)),
+    };
+
+    Ok(running_loop(py)?
+        .call_method1("run_in_executor", (py.None(), job))?
+        .unbind())
+}
+
+/// An already resolved asyncio.Future
+pub fn ready(py: Python<'_>, value: PyObject) -> PyResult<PyObject> {
+    let future = running_loop(py)?.call_method0("create_future")?;
+    future.call_method1("set_result", (value,))?;
+    Ok(future.unbind())
+}
+
+fn running_loop(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    py.import_bound("asyncio")?.call_method0("get_running_loop")
+}
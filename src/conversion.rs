@@ -1,52 +1,155 @@
 // This module handles conversion between Rust and Python types
-// Currently handled in types.rs via from_rust() and into_rust() methods
-// Can be expanded for more complex conversions if needed
+// Message-level conversion lives in types.rs via from_rust() and into_rust() methods,
+// here we render lists of messages into the supported output formats
 
-use crate::types::PyMessage;
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::types::{PyMessage, PyOutputConfig, PyPlatform};
 use pyo3::prelude::*;
+use serde::Serialize;
+
+/// Split messages into runs of consecutive messages sharing the same platform.
+/// chatpack::Message has no platform field, so list-level operations run on each
+/// run separately and re-attach the platform afterwards
+pub fn into_platform_runs(
+    messages: Vec<PyMessage>,
+) -> Vec<(Option<PyPlatform>, Vec<chatpack::Message>)> {
+    let mut runs: Vec<(Option<PyPlatform>, Vec<chatpack::Message>)> = Vec::new();
+
+    for msg in messages {
+        let platform = msg.platform;
+        match runs.last_mut() {
+            Some((last, run)) if *last == platform => run.push(msg.into_rust()),
+            _ => runs.push((platform, vec![msg.into_rust()])),
+        }
+    }
+
+    runs
+}
+
+/// Inverse of `into_platform_runs`
+pub fn from_platform_runs(
+    runs: Vec<(Option<PyPlatform>, Vec<chatpack::Message>)>,
+) -> Vec<PyMessage> {
+    runs.into_iter()
+        .flat_map(|(platform, run)| {
+            run.into_iter()
+                .map(move |m| PyMessage::from_rust(m, platform))
+        })
+        .collect()
+}
+
+/// Flat output row shared by the JSON and JSONL writers
+#[derive(Serialize)]
+struct OutputRow<'a> {
+    sender: &'a str,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform: Option<&'a str>,
+}
+
+impl<'a> OutputRow<'a> {
+    fn new(msg: &'a PyMessage, config: &PyOutputConfig) -> Self {
+        OutputRow {
+            sender: &msg.sender,
+            content: &msg.content,
+            timestamp: if config.include_timestamps {
+                msg.timestamp_rfc3339()
+            } else {
+                None
+            },
+            platform: if config.include_platform {
+                msg.platform.map(|p| p.as_str())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+// Helper: serialisation failures surface as ParseError, like reading failures
+fn output_error(format: &str, err: impl std::fmt::Display) -> PyErr {
+    ErrorInfo::new(ErrorKind::Parse, format!("{} error: {}", format, err)).into()
+}
 
 /// Helper to convert messages to JSON string
-#[allow(dead_code)]
-pub fn messages_to_json(messages: &[PyMessage]) -> PyResult<String> {
-    let rust_messages: Vec<chatpack::Message> =
-        messages.iter().map(|m| m.clone().into_rust()).collect();
+pub fn messages_to_json(messages: &[PyMessage], config: &PyOutputConfig) -> PyResult<String> {
+    let rows: Vec<OutputRow> = messages.iter().map(|m| OutputRow::new(m, config)).collect();
 
-    serde_json::to_string_pretty(&rust_messages)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("JSON error: {}", e)))
+    serde_json::to_string_pretty(&rows).map_err(|e| output_error("JSON", e))
 }
 
-/// Helper to convert messages to CSV string
-#[allow(dead_code)]
-pub fn messages_to_csv(messages: &[PyMessage]) -> PyResult<String> {
-    use std::io::Cursor;
+/// Helper to convert messages to JSONL string (one JSON object per line)
+pub fn messages_to_jsonl(messages: &[PyMessage], config: &PyOutputConfig) -> PyResult<String> {
+    let mut output = String::new();
 
-    let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new())); // Remove rust_messages conversion
+    for msg in messages {
+        let line = serde_json::to_string(&OutputRow::new(msg, config))
+            .map_err(|e| output_error("JSON", e))?;
+        output.push_str(&line);
+        output.push('\n');
+    }
 
-    // let rust_messages: Vec<chatpack::Message> = messages
-    //     .iter()
-    //     .map(|m| m.clone().into_rust())
-    //     .collect();
+    Ok(output)
+}
+
+/// Helper to convert messages to CSV string
+pub fn messages_to_csv(messages: &[PyMessage], config: &PyOutputConfig) -> PyResult<String> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
 
     // Write header
-    wtr.write_record(["sender", "content", "timestamp", "platform"])
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("CSV error: {}", e)))?;
+    let mut header = vec!["sender", "content"];
+    if config.include_timestamps {
+        header.push("timestamp");
+    }
+    if config.include_platform {
+        header.push("platform");
+    }
+    wtr.write_record(&header)
+        .map_err(|e| output_error("CSV", e))?;
 
     // Write rows
     for msg in messages {
-        wtr.write_record(&[
-            msg.sender.clone(),
-            msg.content.clone(),
-            msg.timestamp.clone().unwrap_or_default(),
-            msg.platform.clone().unwrap_or_default(),
-        ])
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("CSV error: {}", e)))?;
+        let timestamp = msg.timestamp_rfc3339();
+        let mut record = vec![msg.sender.as_str(), msg.content.as_str()];
+        if config.include_timestamps {
+            record.push(timestamp.as_deref().unwrap_or_default());
+        }
+        if config.include_platform {
+            record.push(msg.platform.map(|p| p.as_str()).unwrap_or_default());
+        }
+        wtr.write_record(&record)
+            .map_err(|e| output_error("CSV", e))?;
     }
 
-    let data = wtr
-        .into_inner()
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("CSV error: {}", e)))?
-        .into_inner();
+    let data = wtr.into_inner().map_err(|e| output_error("CSV", e))?;
+
+    String::from_utf8(data).map_err(|e| output_error("UTF-8", e))
+}
+
+/// Helper to render messages in the named format ("csv", "json" or "jsonl")
+pub fn render_messages(
+    messages: &[PyMessage],
+    format: &str,
+    config: &PyOutputConfig,
+) -> PyResult<String> {
+    match format.to_ascii_lowercase().as_str() {
+        "csv" => messages_to_csv(messages, config),
+        "json" => messages_to_json(messages, config),
+        "jsonl" => messages_to_jsonl(messages, config),
+        other => Err(ErrorInfo::new(
+            ErrorKind::UnsupportedFormat,
+            format!(
+                "Unknown output format: '{}'. Expected one of: csv, json, jsonl",
+                other
+            ),
+        )
+        .into()),
+    }
+}
 
-    String::from_utf8(data)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("UTF-8 error: {}", e)))
+/// Helper to write rendered output to a file
+pub fn write_output(path: &str, data: &str) -> PyResult<()> {
+    std::fs::write(path, data).map_err(|e| ErrorInfo::io(&e, path.as_ref()).into())
 }
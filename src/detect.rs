@@ -0,0 +1,183 @@
+// Platform detection by sniffing the beginning of an export
+
+use crate::archive;
+use crate::errors::ErrorInfo;
+use crate::instagram;
+use crate::source::read_head;
+use crate::types::PyPlatform;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// How many bytes of an export are inspected
+pub const SNIFF_BYTES: usize = 64 * 1024;
+
+/// How many non-empty lines of a text export are inspected
+const SNIFF_LINES: usize = 200;
+
+pub struct Detection {
+    pub platform: PyPlatform,
+    pub confidence: f64,
+}
+
+/// Guess the platform of an export from its first bytes
+pub fn detect(sample: &[u8]) -> Option<Detection> {
+    let text = String::from_utf8_lossy(sample);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    let scores = if looks_like_json(text) {
+        score_json(text)
+    } else if is_discord_csv(text) {
+        vec![(PyPlatform::Discord, 0.95)]
+    } else {
+        score_text(text)
+    };
+
+    scores
+        .into_iter()
+        .filter(|(_, confidence)| *confidence > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(platform, confidence)| Detection {
+            platform,
+            confidence: confidence.min(1.0),
+        })
+}
+
+/// Detect the platform of a file, a zipped export or a directory
+/// (directories can only be Instagram exports)
+pub fn detect_path(path: &Path) -> Result<Option<Detection>, ErrorInfo> {
+    if path.is_dir() {
+        return Ok(instagram::is_export_dir(path).then_some(Detection {
+            platform: PyPlatform::Instagram,
+            confidence: 1.0,
+        }));
+    }
+
+    let sample = read_head(path, SNIFF_BYTES)?;
+    if archive::is_zip(&sample) {
+        return archive::open_path(path)?.detect();
+    }
+    Ok(detect(&sample))
+}
+
+/// Detect the platform of in-memory content, which may be a zipped export
+pub fn detect_bytes(data: &[u8]) -> Result<Option<Detection>, ErrorInfo> {
+    if archive::is_zip(data) {
+        return archive::open_bytes(data)?.detect();
+    }
+    Ok(detect(&data[..data.len().min(SNIFF_BYTES)]))
+}
+
+// Helper: WhatsApp lines may start with '[' too, so look past the bracket
+fn looks_like_json(text: &str) -> bool {
+    match text.strip_prefix('[') {
+        Some(rest) => rest.trim_start().starts_with(['{', '[', ']']),
+        None => text.starts_with('{'),
+    }
+}
+
+// Helper: JSON exports are told apart by their characteristic keys
+fn score_json(text: &str) -> Vec<(PyPlatform, f64)> {
+    let score = |keys: &[(&str, f64)]| -> f64 {
+        keys.iter()
+            .filter(|(key, _)| has_key(text, key))
+            .map(|(_, weight)| weight)
+            .sum()
+    };
+
+    vec![
+        (
+            PyPlatform::Telegram,
+            score(&[
+                ("messages", 0.3),
+                ("from", 0.4),
+                ("from_id", 0.15),
+                ("date_unixtime", 0.15),
+            ]),
+        ),
+        (
+            PyPlatform::Instagram,
+            score(&[
+                ("participants", 0.4),
+                ("sender_name", 0.45),
+                ("timestamp_ms", 0.15),
+            ]),
+        ),
+        (
+            PyPlatform::Discord,
+            score(&[
+                ("guild", 0.3),
+                ("channel", 0.2),
+                ("author", 0.3),
+                ("messages", 0.2),
+            ]),
+        ),
+    ]
+}
+
+// Helper: does the JSON text contain `"key":` (whitespace allowed before the colon)
+fn has_key(text: &str, key: &str) -> bool {
+    let quoted = format!("\"{}\"", key);
+    text.match_indices(&quoted)
+        .any(|(i, m)| text[i + m.len()..].trim_start().starts_with(':'))
+}
+
+fn is_discord_csv(text: &str) -> bool {
+    let header = text.lines().next().unwrap_or_default();
+    header.starts_with("AuthorID,") || header.starts_with("\"AuthorID\",")
+}
+
+// Helper: text exports are recognised by their line prefixes
+fn score_text(text: &str) -> Vec<(PyPlatform, f64)> {
+    static WHATSAPP: OnceLock<Regex> = OnceLock::new();
+    static DISCORD: OnceLock<Regex> = OnceLock::new();
+
+    // [1/15/24, 10:30:45 AM] Alice: / 15.01.24, 10:30 - Alice: / ...
+    let whatsapp = WHATSAPP.get_or_init(|| {
+        Regex::new(r"^\[?\d{1,2}[./]\d{1,2}[./]\d{2,4},\s\d{1,2}:\d{2}(?::\d{2})?(?:\s?[APap][Mm])?(?:\]\s|\s-\s)[^:]+:")
+            .unwrap()
+    });
+    // [1/15/2024 10:30 AM] Alice (DiscordChatExporter TXT)
+    let discord = DISCORD.get_or_init(|| {
+        Regex::new(r"^\[\d{1,2}/\d{1,2}/\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?\]\s+\S")
+            .unwrap()
+    });
+
+    let mut total = 0usize;
+    let mut whatsapp_hits = 0usize;
+    let mut discord_hits = 0usize;
+    let mut discord_header = false;
+
+    for line in text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(SNIFF_LINES)
+    {
+        total += 1;
+        if whatsapp.is_match(line) {
+            whatsapp_hits += 1;
+        } else if discord.is_match(line) {
+            discord_hits += 1;
+        } else if line.starts_with("Guild: ") || line.starts_with("Channel: ") {
+            discord_header = true;
+        }
+    }
+
+    let ratio_score = |hits: usize| {
+        if hits == 0 {
+            0.0
+        } else {
+            0.5 + 0.5 * hits as f64 / total as f64
+        }
+    };
+
+    let mut discord_score = ratio_score(discord_hits);
+    if discord_header {
+        discord_score += 0.3;
+    }
+
+    vec![
+        (PyPlatform::WhatsApp, ratio_score(whatsapp_hits)),
+        (PyPlatform::Discord, discord_score),
+    ]
+}
@@ -0,0 +1,189 @@
+// Documents for retrieval (RAG): overlapping windows of rendered messages with the
+// metadata a vector store wants to filter on
+//
+// Windows never span an idle gap (a pause longer than `idle_gap` starts a new
+// session); a full window that could go on is cut at its longest pause instead
+
+use crate::conversion::render_messages;
+use crate::types::{PyMessage, PyOutputConfig, PyPlatform};
+use chrono::{DateTime, TimeDelta, Utc};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ops::Range;
+
+/// A chunk of a conversation ready for embedding
+#[pyclass]
+#[derive(Clone)]
+pub struct PyDocument {
+    /// Messages rendered in the requested format
+    #[pyo3(get)]
+    pub text: String,
+
+    #[pyo3(get)]
+    pub start_ts: Option<DateTime<Utc>>,
+
+    #[pyo3(get)]
+    pub end_ts: Option<DateTime<Utc>>,
+
+    /// Senders in order of first appearance
+    #[pyo3(get)]
+    pub participants: Vec<String>,
+
+    pub platform: Option<PyPlatform>,
+
+    /// Export the messages came from
+    #[pyo3(get)]
+    pub source: Option<String>,
+
+    #[pyo3(get)]
+    pub start_id: Option<u64>,
+
+    #[pyo3(get)]
+    pub end_id: Option<u64>,
+
+    #[pyo3(get)]
+    pub message_count: usize,
+}
+
+#[pymethods]
+impl PyDocument {
+    /// Platform name as a string, None if the messages come from several platforms
+    #[getter]
+    fn platform(&self) -> Option<&'static str> {
+        self.platform.map(|p| p.as_str())
+    }
+
+    fn __len__(&self) -> usize {
+        self.message_count
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Document(messages={}, participants={:?}, start_ts={}, end_ts={})",
+            self.message_count,
+            self.participants,
+            self.start_ts
+                .map(|ts| ts.to_rfc3339())
+                .as_deref()
+                .unwrap_or("None"),
+            self.end_ts
+                .map(|ts| ts.to_rfc3339())
+                .as_deref()
+                .unwrap_or("None")
+        )
+    }
+
+    /// Convert to dictionary
+    pub fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("text", &self.text)?;
+        dict.set_item("start_ts", self.start_ts)?;
+        dict.set_item("end_ts", self.end_ts)?;
+        dict.set_item("participants", &self.participants)?;
+        dict.set_item("platform", self.platform())?;
+        dict.set_item("source", &self.source)?;
+        dict.set_item("start_id", self.start_id)?;
+        dict.set_item("end_id", self.end_id)?;
+        dict.set_item("message_count", self.message_count)?;
+        Ok(dict.into())
+    }
+}
+
+impl PyDocument {
+    fn build(messages: &[PyMessage], text: String, source: Option<&str>) -> PyDocument {
+        let mut participants: Vec<String> = Vec::new();
+        for msg in messages {
+            if !participants.contains(&msg.sender) {
+                participants.push(msg.sender.clone());
+            }
+        }
+
+        let timestamps = messages.iter().filter_map(|m| m.timestamp);
+        let ids = messages.iter().filter_map(|m| m.id);
+        let platform = messages
+            .first()
+            .and_then(|first| first.platform)
+            .filter(|p| messages.iter().all(|m| m.platform == Some(*p)));
+
+        PyDocument {
+            text,
+            start_ts: timestamps.clone().min(),
+            end_ts: timestamps.max(),
+            participants,
+            platform,
+            source: source.map(str::to_string),
+            start_id: ids.clone().min(),
+            end_id: ids.max(),
+            message_count: messages.len(),
+        }
+    }
+}
+
+/// Pause before `messages[i]`, None without both timestamps
+fn gap_before(messages: &[PyMessage], i: usize) -> Option<TimeDelta> {
+    Some(messages[i].timestamp? - messages[i - 1].timestamp?)
+}
+
+/// Split messages into windows of up to `window` messages, `stride` apart
+pub fn windows(
+    messages: &[PyMessage],
+    window: usize,
+    stride: usize,
+    idle_gap: TimeDelta,
+) -> Vec<Range<usize>> {
+    let is_idle = |i: usize| gap_before(messages, i).is_some_and(|gap| gap > idle_gap);
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < messages.len() {
+        let limit = (start + window).min(messages.len());
+        let mut end = (start + 1..limit).find(|&i| is_idle(i)).unwrap_or(limit);
+
+        // Full window in the middle of a session: end at the longest pause of its
+        // second half, so the next window starts where the talk picked up again
+        if end == limit && limit < messages.len() && !is_idle(limit) {
+            let cut = (start + window.div_ceil(2)).max(start + 1)..=limit;
+            // Ties go to the later pause, keeping the window longer
+            if let Some((_, i)) = cut
+                .filter_map(|i| Some((gap_before(messages, i)?, i)))
+                .max_by_key(|(gap, _)| *gap)
+            {
+                end = i;
+            }
+        }
+
+        windows.push(start..end);
+        if end == messages.len() {
+            break;
+        }
+
+        // Overlap stays inside a session
+        start = if is_idle(end) {
+            end
+        } else {
+            end.saturating_sub(window - stride).max(start + 1)
+        };
+    }
+
+    windows
+}
+
+/// Render every window into a document
+pub fn documents(
+    messages: &[PyMessage],
+    window: usize,
+    stride: usize,
+    idle_gap: TimeDelta,
+    format: &str,
+    config: &PyOutputConfig,
+    source: Option<&str>,
+) -> PyResult<Vec<PyDocument>> {
+    windows(messages, window, stride, idle_gap)
+        .into_iter()
+        .map(|range| {
+            let messages = &messages[range];
+            let text = render_messages(messages, format, config)?;
+            Ok(PyDocument::build(messages, text, source))
+        })
+        .collect()
+}
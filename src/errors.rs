@@ -0,0 +1,271 @@
+// Python exception hierarchy and mapping from chatpack::ChatpackError
+//
+// ChatpackError(Exception)
+// ├── ParseError(ChatpackError, ValueError)
+// ├── InvalidDateError(ChatpackError, ValueError)
+// ├── UnsupportedFormatError(ChatpackError, ValueError)
+// └── ChatpackIOError(ChatpackError, OSError)
+//
+// The classes need multiple bases, which create_exception! can't express,
+// so they are built with type() once and cached
+
+use crate::types::PyPlatform;
+use chatpack::error::{ParseErrorKind, StreamingErrorKind};
+use chatpack::ChatpackError;
+use pyo3::exceptions::{PyException, PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyDict, PyTuple, PyType};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Base,
+    Parse,
+    InvalidDate,
+    UnsupportedFormat,
+    Io,
+}
+
+struct ErrorTypes {
+    base: Py<PyType>,
+    parse: Py<PyType>,
+    invalid_date: Py<PyType>,
+    unsupported_format: Py<PyType>,
+    io: Py<PyType>,
+}
+
+static ERROR_TYPES: GILOnceCell<ErrorTypes> = GILOnceCell::new();
+
+fn new_type<'py>(
+    py: Python<'py>,
+    name: &str,
+    bases: &[&Bound<'py, PyType>],
+    doc: &str,
+) -> PyResult<Bound<'py, PyType>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("__module__", "chatpack")?;
+    dict.set_item("__doc__", doc)?;
+
+    let ty = py
+        .get_type_bound::<PyType>()
+        .call1((name, PyTuple::new_bound(py, bases), dict))?;
+    Ok(ty.downcast_into::<PyType>()?)
+}
+
+fn error_types(py: Python<'_>) -> PyResult<&ErrorTypes> {
+    ERROR_TYPES.get_or_try_init(py, || {
+        let base = new_type(
+            py,
+            "ChatpackError",
+            &[&py.get_type_bound::<PyException>()],
+            "Base class for all chatpack errors",
+        )?;
+        // Context attributes, filled in when known
+        for attr in ["path", "line", "byte_offset", "platform"] {
+            base.setattr(attr, py.None())?;
+        }
+
+        let value_error = py.get_type_bound::<PyValueError>();
+        let parse = new_type(
+            py,
+            "ParseError",
+            &[&base, &value_error],
+            "The export content is malformed",
+        )?;
+        let invalid_date = new_type(
+            py,
+            "InvalidDateError",
+            &[&base, &value_error],
+            "A date filter could not be parsed",
+        )?;
+        let unsupported_format = new_type(
+            py,
+            "UnsupportedFormatError",
+            &[&base, &value_error],
+            "The export or output format is not supported",
+        )?;
+        let io = new_type(
+            py,
+            "ChatpackIOError",
+            &[&base, &py.get_type_bound::<PyOSError>()],
+            "Reading or writing a file failed",
+        )?;
+
+        Ok(ErrorTypes {
+            base: base.unbind(),
+            parse: parse.unbind(),
+            invalid_date: invalid_date.unbind(),
+            unsupported_format: unsupported_format.unbind(),
+            io: io.unbind(),
+        })
+    })
+}
+
+/// Add the exception classes to the module
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let types = error_types(py)?;
+
+    m.add("ChatpackError", types.base.bind(py))?;
+    m.add("ParseError", types.parse.bind(py))?;
+    m.add("InvalidDateError", types.invalid_date.bind(py))?;
+    m.add("UnsupportedFormatError", types.unsupported_format.bind(py))?;
+    m.add("ChatpackIOError", types.io.bind(py))?;
+
+    Ok(())
+}
+
+/// Error details collected on the Rust side, turned into a Python exception
+/// only when it crosses the boundary (so it can be built without the GIL)
+#[derive(Debug)]
+pub struct ErrorInfo {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    pub line: Option<u64>,
+    pub byte_offset: Option<u64>,
+    pub platform: Option<PyPlatform>,
+    pub errno: Option<i32>,
+}
+
+impl ErrorInfo {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        ErrorInfo {
+            kind,
+            message: message.into(),
+            path: None,
+            line: None,
+            byte_offset: None,
+            platform: None,
+            errno: None,
+        }
+    }
+
+    pub fn io(err: &std::io::Error, path: &Path) -> Self {
+        ErrorInfo {
+            errno: err.raw_os_error(),
+            ..ErrorInfo::new(ErrorKind::Io, err.to_string())
+        }
+        .with_path(path)
+    }
+
+    /// Set the path unless the upstream error already carried one
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path.get_or_insert_with(|| path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the platform unless it is already known
+    pub fn with_platform(mut self, platform: PyPlatform) -> Self {
+        self.platform.get_or_insert(platform);
+        self
+    }
+
+    pub fn with_byte_offset(mut self, offset: u64) -> Self {
+        self.byte_offset.get_or_insert(offset);
+        self
+    }
+
+    fn into_pyerr(self, py: Python<'_>) -> PyResult<PyErr> {
+        let types = error_types(py)?;
+        let ty = match self.kind {
+            ErrorKind::Base => &types.base,
+            ErrorKind::Parse => &types.parse,
+            ErrorKind::InvalidDate => &types.invalid_date,
+            ErrorKind::UnsupportedFormat => &types.unsupported_format,
+            ErrorKind::Io => &types.io,
+        }
+        .bind(py);
+
+        // OSError(errno, strerror, filename) fills in .errno/.strerror/.filename
+        let exc = match (self.kind, self.errno) {
+            (ErrorKind::Io, Some(errno)) => {
+                ty.call1((errno, &self.message, self.path.as_deref()))?
+            }
+            _ => ty.call1((&self.message,))?,
+        };
+
+        exc.setattr("path", self.path.as_deref())?;
+        exc.setattr("line", self.line)?;
+        exc.setattr("byte_offset", self.byte_offset)?;
+        exc.setattr("platform", self.platform.map(|p| p.as_str()))?;
+
+        Ok(PyErr::from_value_bound(exc))
+    }
+}
+
+impl From<ErrorInfo> for PyErr {
+    fn from(info: ErrorInfo) -> Self {
+        Python::with_gil(|py| info.into_pyerr(py).unwrap_or_else(|e| e))
+    }
+}
+
+// Helper: "Telegram JSON" / "WhatsApp TXT" -> platform
+fn platform_from_format(format: &str) -> Option<PyPlatform> {
+    format.split_whitespace().next()?.parse().ok()
+}
+
+impl From<ChatpackError> for ErrorInfo {
+    #[allow(unreachable_patterns)]
+    fn from(err: ChatpackError) -> Self {
+        let message = err.to_string();
+
+        match err {
+            ChatpackError::Io(e) => ErrorInfo {
+                errno: e.raw_os_error(),
+                ..ErrorInfo::new(ErrorKind::Io, message)
+            },
+            ChatpackError::Parse {
+                format,
+                source,
+                path,
+            } => ErrorInfo {
+                path,
+                platform: platform_from_format(format),
+                line: match &source {
+                    ParseErrorKind::Json(e) => Some(e.line() as u64),
+                    _ => None,
+                },
+                ..ErrorInfo::new(ErrorKind::Parse, message)
+            },
+            ChatpackError::InvalidFormat { format, .. } => ErrorInfo {
+                platform: platform_from_format(format),
+                ..ErrorInfo::new(ErrorKind::UnsupportedFormat, message)
+            },
+            ChatpackError::InvalidDate { .. } => ErrorInfo::new(ErrorKind::InvalidDate, message),
+            ChatpackError::Csv(e) => ErrorInfo {
+                line: e.position().map(|p| p.line()),
+                byte_offset: e.position().map(|p| p.byte()),
+                ..ErrorInfo::new(ErrorKind::Parse, message)
+            },
+            ChatpackError::Json(e) => ErrorInfo {
+                line: Some(e.line() as u64),
+                ..ErrorInfo::new(ErrorKind::Parse, message)
+            },
+            ChatpackError::Streaming(StreamingErrorKind::Io(e)) => ErrorInfo {
+                errno: e.raw_os_error(),
+                ..ErrorInfo::new(ErrorKind::Io, message)
+            },
+            ChatpackError::Streaming(StreamingErrorKind::Json(e)) => ErrorInfo {
+                line: Some(e.line() as u64),
+                ..ErrorInfo::new(ErrorKind::Parse, message)
+            },
+            ChatpackError::Streaming(StreamingErrorKind::InvalidFormat(_)) => {
+                ErrorInfo::new(ErrorKind::UnsupportedFormat, message)
+            }
+            ChatpackError::Streaming(_)
+            | ChatpackError::Utf8 { .. }
+            | ChatpackError::BufferOverflow { .. }
+            | ChatpackError::UnexpectedEof { .. } => ErrorInfo::new(ErrorKind::Parse, message),
+            // ChatpackError is #[non_exhaustive] upstream
+            _ => ErrorInfo::new(ErrorKind::Base, message),
+        }
+    }
+}
+
+impl From<chatpack::streaming::StreamingError> for ErrorInfo {
+    fn from(err: chatpack::streaming::StreamingError) -> Self {
+        ChatpackError::from(err).into()
+    }
+}
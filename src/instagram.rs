@@ -0,0 +1,156 @@
+// Instagram conversation directories: one conversation is split into
+// message_1.json (newest) ... message_N.json (oldest), and the inbox/ root
+// holds one such directory per conversation
+
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::parsers::{Parsed, Pipeline};
+use crate::types::PyPlatform;
+use chatpack::parser::Parser;
+use chatpack::parsing::instagram::fix_mojibake_encoding;
+use chatpack::Message;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+struct ConversationInfo {
+    title: Option<String>,
+}
+
+/// Part number of a message_N.json file name
+pub fn part_number(name: &str) -> Option<u32> {
+    name.strip_prefix("message_")?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+}
+
+/// Parts of a conversation, oldest first (message_N.json down to message_1.json)
+fn conversation_parts(dir: &Path) -> Result<Vec<PathBuf>, ErrorInfo> {
+    let mut parts = Vec::new();
+
+    for entry in std::fs::read_dir(dir).map_err(|e| ErrorInfo::io(&e, dir))? {
+        let path = entry.map_err(|e| ErrorInfo::io(&e, dir))?.path();
+        let number = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(part_number);
+
+        if let Some(number) = number {
+            parts.push((number, path));
+        }
+    }
+
+    parts.sort_by_key(|(number, _)| std::cmp::Reverse(*number));
+    Ok(parts.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Conversation directories under `path`: the directory itself if it holds
+/// message_N.json parts, otherwise its subdirectories that do (inbox/ root)
+fn conversation_dirs(path: &Path) -> Result<Vec<PathBuf>, ErrorInfo> {
+    if !conversation_parts(path)?.is_empty() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(|e| ErrorInfo::io(&e, path))? {
+        let dir = entry.map_err(|e| ErrorInfo::io(&e, path))?.path();
+        if dir.is_dir() && !conversation_parts(&dir)?.is_empty() {
+            dirs.push(dir);
+        }
+    }
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Does the directory look like an Instagram conversation or inbox
+pub fn is_export_dir(path: &Path) -> bool {
+    conversation_dirs(path).is_ok_and(|dirs| !dirs.is_empty())
+}
+
+/// Parse a conversation directory or the inbox/ root.
+/// Parts are stitched in chronological order and deduplicated, every
+/// message gets the conversation title
+pub fn parse_directory(
+    parser: &dyn Parser,
+    pipeline: &Pipeline,
+    path: &Path,
+) -> Result<Parsed, ErrorInfo> {
+    let dirs = conversation_dirs(path)?;
+    if dirs.is_empty() {
+        return Err(ErrorInfo::new(
+            ErrorKind::UnsupportedFormat,
+            "No Instagram message_N.json files found",
+        )
+        .with_path(path)
+        .with_platform(PyPlatform::Instagram));
+    }
+
+    let mut result = Parsed::new(PyPlatform::Instagram);
+    for dir in dirs {
+        let parts = conversation_parts(&dir)?.into_iter().map(|part| {
+            let content = std::fs::read_to_string(&part).map_err(|e| ErrorInfo::io(&e, &part))?;
+            Ok((part, content))
+        });
+        let (messages, title) = parse_conversation(parser, pipeline, parts)?;
+        result.push(messages, title);
+    }
+
+    Ok(result)
+}
+
+/// Stitch the parts of one conversation (oldest first, as (path, content)),
+/// deduplicate and run the pipeline, returning the messages and the title
+pub fn parse_conversation(
+    parser: &dyn Parser,
+    pipeline: &Pipeline,
+    parts: impl IntoIterator<Item = Result<(PathBuf, String), ErrorInfo>>,
+) -> Result<(Vec<Message>, Option<String>), ErrorInfo> {
+    let mut title = None;
+    let mut messages = Vec::new();
+    // Where the previous part starts in `messages`
+    let mut previous = 0;
+
+    for part in parts {
+        let (part, content) = part?;
+        let with_context = |e: ErrorInfo| e.with_path(&part).with_platform(PyPlatform::Instagram);
+
+        if title.is_none() {
+            let info: ConversationInfo = serde_json::from_str(&content)
+                .map_err(|e| with_context(chatpack::ChatpackError::from(e).into()))?;
+            title = info.title.map(|t| fix_mojibake_encoding(&t));
+        }
+
+        let part_messages = parser
+            .parse_str(&content)
+            .map_err(|e| with_context(e.into()))?;
+
+        // Neighbouring parts may overlap at their edges, repeats inside a part are real
+        let skip = overlap(&messages[previous..], &part_messages);
+        previous = messages.len();
+        messages.extend(part_messages.into_iter().skip(skip));
+    }
+
+    // Parts are already oldest first, the stable sort only fixes stragglers.
+    // Messages without a timestamp go last, in file order
+    messages.sort_by_key(|m| (m.timestamp.is_none(), m.timestamp));
+
+    // Переписки обрабатываются по отдельности, чтобы merge не склеивал разные чаты
+    Ok((pipeline.run(messages), title))
+}
+
+/// Length of the longest tail of `previous` that `next` starts with
+fn overlap(previous: &[Message], next: &[Message]) -> usize {
+    let same = |a: &Message, b: &Message| {
+        a.sender == b.sender && a.timestamp == b.timestamp && a.content == b.content
+    };
+    (1..=previous.len().min(next.len()))
+        .rev()
+        .find(|&n| {
+            previous[previous.len() - n..]
+                .iter()
+                .zip(next)
+                .all(|(a, b)| same(a, b))
+        })
+        .unwrap_or(0)
+}
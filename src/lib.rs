@@ -1,9 +1,28 @@
 #![allow(clippy::useless_conversion)]
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
+mod archive;
+mod arrow;
+mod asyncio;
 mod conversion;
+mod detect;
+mod documents;
+mod errors;
+mod instagram;
+mod lines;
+mod parallel;
+mod parquet;
 mod parsers;
+mod processing;
+mod redact;
+mod sessions;
+mod source;
 mod streaming; // <-- Добавляем модуль
+mod tokens;
 mod types;
 
 use parsers::*;
@@ -12,10 +31,18 @@ use types::*;
 
 #[pymodule]
 fn _chatpack(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Register exceptions
+    errors::register(m)?;
+
     // Register types
     m.add_class::<PyMessage>()?;
+    m.add_class::<PyPlatform>()?;
     m.add_class::<PyFilterConfig>()?;
+    m.add_class::<PyMergeConfig>()?;
     m.add_class::<PyOutputConfig>()?;
+    m.add_class::<documents::PyDocument>()?;
+    m.add_class::<sessions::PySession>()?;
+    m.add_class::<redact::PyRedactor>()?;
 
     // Register parsers
     m.add_class::<TelegramParser>()?;
@@ -34,105 +61,604 @@ fn _chatpack(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_whatsapp, m)?)?;
     m.add_function(wrap_pyfunction!(parse_instagram, m)?)?;
     m.add_function(wrap_pyfunction!(parse_discord, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_auto, m)?)?;
+    m.add_function(wrap_pyfunction!(async_parse_telegram, m)?)?;
+    m.add_function(wrap_pyfunction!(async_parse_whatsapp, m)?)?;
+    m.add_function(wrap_pyfunction!(async_parse_instagram, m)?)?;
+    m.add_function(wrap_pyfunction!(async_parse_discord, m)?)?;
+    m.add_function(wrap_pyfunction!(async_parse_auto, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_platform, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_many, m)?)?;
+    m.add_function(wrap_pyfunction!(referenced_media, m)?)?;
+
+    // Arrow / DataFrame export
+    m.add_function(wrap_pyfunction!(to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(to_pandas, m)?)?;
+    m.add_function(wrap_pyfunction!(to_polars, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_telegram_to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_whatsapp_to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_instagram_to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_discord_to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_auto_to_arrow, m)?)?;
 
     // Utility functions
     m.add_function(wrap_pyfunction!(merge_consecutive, m)?)?;
     m.add_function(wrap_pyfunction!(apply_filters, m)?)?;
+    m.add_function(wrap_pyfunction!(count_tokens, m)?)?;
+    m.add_function(wrap_pyfunction!(chunk_by_tokens, m)?)?;
+    m.add_function(wrap_pyfunction!(to_documents, m)?)?;
+    m.add_function(wrap_pyfunction!(split_sessions, m)?)?;
+    m.add_function(wrap_pyfunction!(redact_messages, m)?)?;
+
+    // Output writers
+    m.add_function(wrap_pyfunction!(write_csv, m)?)?;
+    m.add_function(wrap_pyfunction!(write_json, m)?)?;
+    m.add_function(wrap_pyfunction!(write_jsonl, m)?)?;
+    m.add_function(wrap_pyfunction!(to_csv_string, m)?)?;
+    m.add_function(wrap_pyfunction!(to_json_string, m)?)?;
+    m.add_function(wrap_pyfunction!(to_jsonl_string, m)?)?;
+    m.add_function(wrap_pyfunction!(write_parquet, m)?)?;
+    m.add_function(wrap_pyfunction!(write_parquet_stream, m)?)?;
 
     Ok(())
 }
 
-// ... Функции parse_telegram и другие из parsers.rs подключаются через use parsers::*;
-// НО нам нужно экспортировать их как pyfunction здесь.
-// Копируем сигнатуры из предыдущего lib.rs, но ссылаемся на parsers::impl
+// parse_<platform>, async_parse_<platform> and parse_<platform>_to_arrow are generated
+// per platform, `None` detects the platform of the export
+macro_rules! parse_functions {
+    ($doc:literal, $platform:expr, $parse:ident, $async_parse:ident, $to_arrow:ident) => {
+        #[doc = $doc]
+        #[pyfunction]
+        #[pyo3(signature = (path, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+        fn $parse(
+            path: source::InputSource<'_>,
+            merge: Option<MergeArg>,
+            min_length: Option<usize>,
+            max_length: Option<usize>,
+            date_from: Option<String>,
+            date_to: Option<String>,
+            redact: Option<redact::RedactArg>,
+        ) -> PyResult<Vec<PyMessage>> {
+            let messages = parse_impl(
+                $platform,
+                path,
+                MergeArg::resolve(merge),
+                min_length,
+                max_length,
+                date_from,
+                date_to,
+            )?;
+            redact::apply(redact, messages.into_messages())
+        }
+
+        // The parse runs on a worker thread with the GIL released and returns an
+        // asyncio.Future resolved on completion; the package wraps it in a coroutine
+        #[pyfunction]
+        #[pyo3(signature = (path, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+        #[allow(clippy::too_many_arguments)]
+        fn $async_parse(
+            py: Python<'_>,
+            path: PyObject,
+            merge: Option<MergeArg>,
+            min_length: Option<usize>,
+            max_length: Option<usize>,
+            date_from: Option<String>,
+            date_to: Option<String>,
+            redact: Option<redact::RedactArg>,
+        ) -> PyResult<PyObject> {
+            let merge = MergeArg::resolve(merge);
+            asyncio::spawn(py, move |py| {
+                let messages = parse_impl(
+                    $platform,
+                    path.extract(py)?,
+                    merge,
+                    min_length,
+                    max_length,
+                    date_from,
+                    date_to,
+                )?;
+                Ok(redact::apply(redact, messages.into_messages())?.into_py(py))
+            })
+        }
+
+        // Parsed messages go straight into Arrow arrays,
+        // no Python Message objects are created
+        #[pyfunction]
+        #[pyo3(signature = (path, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+        #[allow(clippy::too_many_arguments)]
+        fn $to_arrow(
+            py: Python<'_>,
+            path: source::InputSource<'_>,
+            merge: Option<MergeArg>,
+            min_length: Option<usize>,
+            max_length: Option<usize>,
+            date_from: Option<String>,
+            date_to: Option<String>,
+            redact: Option<redact::RedactArg>,
+        ) -> PyResult<PyObject> {
+            let redactor = redact::RedactArg::resolve(redact)?;
+            let parsed = parse_impl(
+                $platform,
+                path,
+                MergeArg::resolve(merge),
+                min_length,
+                max_length,
+                date_from,
+                date_to,
+            )?;
+            let parsed = redact::apply_parsed(redactor.as_ref(), parsed);
+            arrow::parsed_table(py, &parsed)
+        }
+    };
+}
 
+parse_functions!(
+    "Parse a Telegram JSON export",
+    Some(PyPlatform::Telegram),
+    parse_telegram,
+    async_parse_telegram,
+    parse_telegram_to_arrow
+);
+parse_functions!(
+    "Parse a WhatsApp TXT export",
+    Some(PyPlatform::WhatsApp),
+    parse_whatsapp,
+    async_parse_whatsapp,
+    parse_whatsapp_to_arrow
+);
+parse_functions!(
+    "Parse an Instagram JSON export or conversation directory",
+    Some(PyPlatform::Instagram),
+    parse_instagram,
+    async_parse_instagram,
+    parse_instagram_to_arrow
+);
+parse_functions!(
+    "Parse a Discord export",
+    Some(PyPlatform::Discord),
+    parse_discord,
+    async_parse_discord,
+    parse_discord_to_arrow
+);
+
+// Helper: detect the platform of a source with the GIL released
+fn detect_source(path: &source::InputSource<'_>) -> PyResult<Option<detect::Detection>> {
+    match path.path() {
+        Some(path) => parsers::without_gil(|| detect::detect_path(path)),
+        None => path.with_bytes(|data| parsers::without_gil(|| detect::detect_bytes(data))),
+    }
+}
+
+/// Detect the platform of an export, returning (platform, confidence)
 #[pyfunction]
-#[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-fn parse_telegram(
-    path: String,
-    merge: bool,
+fn detect_platform(path: source::InputSource<'_>) -> PyResult<(Option<PyPlatform>, f64)> {
+    Ok(match detect_source(&path)? {
+        Some(detection) => (Some(detection.platform), detection.confidence),
+        None => (None, 0.0),
+    })
+}
+
+parse_functions!(
+    "Parse an export of unknown platform",
+    None,
+    parse_auto,
+    async_parse_auto,
+    parse_auto_to_arrow
+);
+
+// Helper: parse with the given platform, or the detected one
+fn parse_impl(
+    platform: Option<PyPlatform>,
+    path: source::InputSource<'_>,
+    merge: Option<processing::MergeOptions>,
     min_length: Option<usize>,
+    max_length: Option<usize>,
     date_from: Option<String>,
     date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    parsers::parse_telegram_impl(path, merge, min_length, date_from, date_to)
+) -> PyResult<parsers::Parsed> {
+    let platform = match platform {
+        Some(platform) => platform,
+        None => {
+            detect_source(&path)?
+                .ok_or_else(|| {
+                    let info = errors::ErrorInfo::new(
+                        errors::ErrorKind::UnsupportedFormat,
+                        "Could not detect the chat platform of the export",
+                    );
+                    match path.path() {
+                        Some(p) => info.with_path(p),
+                        None => info,
+                    }
+                })?
+                .platform
+        }
+    };
+
+    parsers::parse_platform_impl(
+        platform, path, merge, min_length, max_length, date_from, date_to,
+    )
 }
 
+/// Parse many exports in parallel, returning (messages, errors).
+/// `messages` is one chronological list, or a dict keyed by path with by_path=True;
+/// `errors` maps the paths that failed to their exceptions
 #[pyfunction]
-#[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-fn parse_whatsapp(
-    path: String,
-    merge: bool,
+#[pyo3(signature = (paths, platform=None, workers=None, by_path=false, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+#[allow(clippy::too_many_arguments)]
+fn parse_many(
+    py: Python<'_>,
+    paths: Vec<PathBuf>,
+    platform: Option<PlatformArg>,
+    workers: Option<usize>,
+    by_path: bool,
+    merge: Option<MergeArg>,
     min_length: Option<usize>,
+    max_length: Option<usize>,
     date_from: Option<String>,
     date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    parsers::parse_whatsapp_impl(path, merge, min_length, date_from, date_to)
+    redact: Option<redact::RedactArg>,
+) -> PyResult<(PyObject, Py<PyDict>)> {
+    let platform = platform.map(PlatformArg::resolve).transpose()?;
+    if workers == Some(0) {
+        return Err(PyValueError::new_err("workers must be positive"));
+    }
+    let workers = workers.unwrap_or_else(parallel::default_workers);
+    let pipeline = parsers::Pipeline::from_args(
+        MergeArg::resolve(merge),
+        min_length,
+        max_length,
+        date_from,
+        date_to,
+    )?;
+
+    let redactor = redact::RedactArg::resolve(redact)?;
+
+    let results = py.allow_threads(|| {
+        let mut results = parallel::parse_files(&paths, platform, &pipeline, workers);
+        // In input order, so placeholders don't depend on which file finished first
+        if let Some(redactor) = &redactor {
+            for messages in results.iter_mut().flatten() {
+                redactor.inner.redact_messages(messages);
+            }
+        }
+        results
+    });
+
+    // Ошибки по файлам собираются, а не прерывают весь разбор
+    let errors = PyDict::new_bound(py);
+    let mut parsed = Vec::with_capacity(results.len());
+    for (path, result) in paths.iter().zip(results) {
+        match result {
+            Ok(messages) => parsed.push((path, messages)),
+            Err(info) => {
+                errors.set_item(path.display().to_string(), PyErr::from(info).into_value(py))?
+            }
+        }
+    }
+
+    let messages = if by_path {
+        let dict = PyDict::new_bound(py);
+        for (path, messages) in parsed {
+            dict.set_item(path.display().to_string(), messages.into_py(py))?;
+        }
+        dict.into_any().unbind()
+    } else {
+        let files = parsed.into_iter().map(|(_, messages)| messages);
+        py.allow_threads(|| parallel::chronological(files))
+            .into_py(py)
+    };
+
+    Ok((messages, errors.unbind()))
 }
 
+/// Media files of a zipped export that the messages refer to by file name,
+/// mapping each archive entry to the indices of the messages mentioning it
 #[pyfunction]
-#[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-fn parse_instagram(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    parsers::parse_instagram_impl(path, merge, min_length, date_from, date_to)
+fn referenced_media(
+    path: source::InputSource<'_>,
+    messages: Vec<PyMessage>,
+) -> PyResult<BTreeMap<String, Vec<usize>>> {
+    let not_zip =
+        || errors::ErrorInfo::new(errors::ErrorKind::UnsupportedFormat, "Not a zip archive");
+
+    match path.path() {
+        Some(path) => parsers::without_gil(|| {
+            if !archive::is_zip_file(path) {
+                return Err(not_zip().with_path(path));
+            }
+            archive::open_path(path)?.referenced_media(&messages)
+        }),
+        None => path.with_bytes(|data| {
+            parsers::without_gil(|| {
+                if !archive::is_zip(data) {
+                    return Err(not_zip());
+                }
+                archive::open_bytes(data)?.referenced_media(&messages)
+            })
+        }),
+    }
 }
 
+/// Count the tokens of messages rendered in `format` (cl100k by default)
 #[pyfunction]
-#[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-fn parse_discord(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
+#[pyo3(signature = (messages, format="csv", tokenizer=None, config=None))]
+fn count_tokens(
+    py: Python<'_>,
+    messages: Vec<PyMessage>,
+    format: &str,
+    tokenizer: Option<tokens::Tokenizer>,
+    config: Option<PyOutputConfig>,
+) -> PyResult<usize> {
+    tokens::count_messages(
+        py,
+        &messages,
+        format,
+        &config.unwrap_or_default(),
+        &tokenizer.unwrap_or(tokens::Tokenizer::Cl100k),
+    )
+}
+
+/// Split messages into prompt-sized windows of at most `max_tokens` tokens
+#[pyfunction]
+#[pyo3(signature = (messages, max_tokens, overlap_messages=0, format="csv", tokenizer=None, config=None))]
+fn chunk_by_tokens(
+    py: Python<'_>,
+    messages: Vec<PyMessage>,
+    max_tokens: usize,
+    overlap_messages: usize,
+    format: &str,
+    tokenizer: Option<tokens::Tokenizer>,
+    config: Option<PyOutputConfig>,
+) -> PyResult<Vec<Vec<PyMessage>>> {
+    if max_tokens == 0 {
+        return Err(PyValueError::new_err("max_tokens must be positive"));
+    }
+
+    let windows = tokens::chunk_messages(
+        py,
+        &messages,
+        max_tokens,
+        overlap_messages,
+        format,
+        &config.unwrap_or_default(),
+        &tokenizer.unwrap_or(tokens::Tokenizer::Cl100k),
+    )?;
+
+    Ok(windows
+        .into_iter()
+        .map(|window| messages[window].to_vec())
+        .collect())
+}
+
+/// Split messages into documents of up to `window` messages for vector stores
+#[pyfunction]
+#[pyo3(signature = (messages, window=20, stride=None, idle_gap=chrono::TimeDelta::hours(2), format="csv", config=None, source=None))]
+#[allow(clippy::too_many_arguments)]
+fn to_documents(
+    py: Python<'_>,
+    messages: Vec<PyMessage>,
+    window: usize,
+    stride: Option<usize>,
+    idle_gap: chrono::TimeDelta,
+    format: &str,
+    config: Option<PyOutputConfig>,
+    source: Option<PathBuf>,
+) -> PyResult<Vec<documents::PyDocument>> {
+    let stride = stride.unwrap_or(window);
+    if window == 0 || stride == 0 || stride > window {
+        return Err(PyValueError::new_err(
+            "window must be positive and stride between 1 and window",
+        ));
+    }
+
+    let source = source.map(|path| path.to_string_lossy().into_owned());
+    let config = config.unwrap_or_default();
+    py.allow_threads(|| {
+        documents::documents(
+            &messages,
+            window,
+            stride,
+            idle_gap,
+            format,
+            &config,
+            source.as_deref(),
+        )
+    })
+}
+
+/// Group messages into conversation sessions split by pauses longer than `gap`
+#[pyfunction]
+#[pyo3(signature = (messages, gap=chrono::TimeDelta::hours(2)))]
+fn split_sessions(
+    py: Python<'_>,
+    messages: Vec<PyMessage>,
+    gap: chrono::TimeDelta,
+) -> PyResult<Vec<sessions::PySession>> {
+    let gap = sessions::check_gap(gap)?;
+    Ok(py.allow_threads(|| sessions::split(messages, gap)))
+}
+
+/// Redacted copies of the messages (all built-in detectors, placeholders by default)
+#[pyfunction]
+#[pyo3(name = "redact", signature = (messages, redactor=None))]
+fn redact_messages(
+    messages: Vec<PyMessage>,
+    redactor: Option<redact::PyRedactor>,
 ) -> PyResult<Vec<PyMessage>> {
-    parsers::parse_discord_impl(path, merge, min_length, date_from, date_to)
+    let redactor = match redactor {
+        Some(redactor) => redact::RedactArg::Redactor(redactor),
+        None => redact::RedactArg::Flag(true),
+    };
+    redact::apply(Some(redactor), messages)
+}
+
+/// Convert messages to a pyarrow.Table
+#[pyfunction]
+fn to_arrow(py: Python<'_>, messages: Vec<PyMessage>) -> PyResult<PyObject> {
+    arrow::table(py, &messages)
+}
+
+/// Convert messages to a pandas.DataFrame
+#[pyfunction]
+fn to_pandas(py: Python<'_>, messages: Vec<PyMessage>) -> PyResult<PyObject> {
+    arrow::pandas(py, &messages)
+}
+
+/// Convert messages to a polars.DataFrame
+#[pyfunction]
+fn to_polars(py: Python<'_>, messages: Vec<PyMessage>) -> PyResult<PyObject> {
+    arrow::polars(py, &messages)
 }
 
 /// Merge consecutive messages
 #[pyfunction]
-#[pyo3(signature = (messages, _time_threshold=300))] // time_threshold игнорируется в chatpack 0.5 (дефолт)
-fn merge_consecutive(messages: Vec<PyMessage>, _time_threshold: i64) -> PyResult<Vec<PyMessage>> {
-    // Конвертируем PyMessage -> chatpack::Message
-    let rust_messages: Vec<chatpack::Message> =
-        messages.into_iter().map(|m| m.into_rust()).collect();
+#[pyo3(signature = (messages, time_threshold=Some(300), max_merged_length=None, separator="\n".to_string()))]
+fn merge_consecutive(
+    messages: Vec<PyMessage>,
+    time_threshold: Option<i64>,
+    max_merged_length: Option<usize>,
+    separator: String,
+) -> PyResult<Vec<PyMessage>> {
+    let options = processing::MergeOptions {
+        time_threshold,
+        max_merged_length,
+        separator,
+    };
+
+    parsers::without_gil(|| {
+        // Конвертируем PyMessage -> chatpack::Message, группируя по платформе
+        let runs = conversion::into_platform_runs(messages);
 
-    // Вызываем реальную функцию!
-    let merged = chatpack::prelude::merge_consecutive(rust_messages);
+        // Сообщения разных платформ не склеиваются
+        let merged = runs
+            .into_iter()
+            .map(|(platform, run)| (platform, processing::merge_consecutive(run, &options)))
+            .collect();
 
-    // Конвертируем обратно
-    Ok(merged.into_iter().map(PyMessage::from_rust).collect())
+        // Конвертируем обратно
+        Ok(conversion::from_platform_runs(merged))
+    })
 }
 
 /// Apply filters to messages
 #[pyfunction]
 fn apply_filters(messages: Vec<PyMessage>, config: PyFilterConfig) -> PyResult<Vec<PyMessage>> {
-    // 1. Конвертируем сообщения в Rust
-    let rust_messages: Vec<chatpack::Message> =
-        messages.into_iter().map(|m| m.into_rust()).collect();
+    // 1. Получаем конфиг (он отфильтрует даты и отправителя)
+    let length_filter = config.length_filter();
+    let rust_config = config.into_rust()?;
 
-    // 2. Получаем конфиг (он отфильтрует даты и отправителя)
-    let rust_config = config.clone().into_rust()?; // clone нужен т.к. нам еще нужен min_length
-    let filtered_base = chatpack::core::filter::apply_filters(rust_messages, &rust_config);
+    parsers::without_gil(|| {
+        // 2. Конвертируем сообщения в Rust
+        let runs = conversion::into_platform_runs(messages);
 
-    // 3. Ручная фильтрация длины (т.к. метод в либе может отсутствовать)
-    let final_filtered = if let Some(min_len) = config.min_length {
-        filtered_base
+        let filtered = runs
             .into_iter()
-            .filter(|m| m.content.chars().count() >= min_len)
-            .collect()
-    } else {
-        filtered_base
-    };
+            .map(|(platform, run)| {
+                let filtered_base = chatpack::core::filter::apply_filters(run, &rust_config);
 
-    // 4. Возвращаем в Python
-    Ok(final_filtered
-        .into_iter()
-        .map(PyMessage::from_rust)
-        .collect())
+                // 3. Фильтрация длины (min/max/truncate), которой нет в chatpack 0.5
+                (platform, length_filter.apply(filtered_base))
+            })
+            .collect();
+
+        // 4. Возвращаем в Python
+        Ok(conversion::from_platform_runs(filtered))
+    })
+}
+
+/// Render messages as CSV
+#[pyfunction]
+#[pyo3(signature = (messages, config=None))]
+fn to_csv_string(messages: Vec<PyMessage>, config: Option<PyOutputConfig>) -> PyResult<String> {
+    conversion::messages_to_csv(&messages, &config.unwrap_or_default())
+}
+
+/// Render messages as a pretty-printed JSON array
+#[pyfunction]
+#[pyo3(signature = (messages, config=None))]
+fn to_json_string(messages: Vec<PyMessage>, config: Option<PyOutputConfig>) -> PyResult<String> {
+    conversion::messages_to_json(&messages, &config.unwrap_or_default())
+}
+
+/// Render messages as JSONL (one object per line)
+#[pyfunction]
+#[pyo3(signature = (messages, config=None))]
+fn to_jsonl_string(messages: Vec<PyMessage>, config: Option<PyOutputConfig>) -> PyResult<String> {
+    conversion::messages_to_jsonl(&messages, &config.unwrap_or_default())
+}
+
+/// Write messages to a CSV file
+#[pyfunction]
+#[pyo3(signature = (messages, path, config=None))]
+fn write_csv(
+    messages: Vec<PyMessage>,
+    path: String,
+    config: Option<PyOutputConfig>,
+) -> PyResult<()> {
+    let data = conversion::messages_to_csv(&messages, &config.unwrap_or_default())?;
+    conversion::write_output(&path, &data)
+}
+
+/// Write messages to a JSON file
+#[pyfunction]
+#[pyo3(signature = (messages, path, config=None))]
+fn write_json(
+    messages: Vec<PyMessage>,
+    path: String,
+    config: Option<PyOutputConfig>,
+) -> PyResult<()> {
+    let data = conversion::messages_to_json(&messages, &config.unwrap_or_default())?;
+    conversion::write_output(&path, &data)
+}
+
+/// Write messages to a JSONL file
+#[pyfunction]
+#[pyo3(signature = (messages, path, config=None))]
+fn write_jsonl(
+    messages: Vec<PyMessage>,
+    path: String,
+    config: Option<PyOutputConfig>,
+) -> PyResult<()> {
+    let data = conversion::messages_to_jsonl(&messages, &config.unwrap_or_default())?;
+    conversion::write_output(&path, &data)
+}
+
+/// Write messages to a Parquet file
+#[pyfunction]
+#[pyo3(signature = (messages, path, compression="zstd"))]
+fn write_parquet(messages: Vec<PyMessage>, path: PathBuf, compression: &str) -> PyResult<()> {
+    let compression = parquet::compression(compression)?;
+    parsers::without_gil(|| parquet::write(&messages, &path, compression))
+}
+
+/// Write everything a stream parser yields to a Parquet file, one row group
+/// per `row_group_size` messages. Returns the number of messages written
+#[pyfunction]
+#[pyo3(signature = (stream, path, compression="zstd", row_group_size=100_000))]
+fn write_parquet_stream(
+    stream: AnyStreamParser<'_>,
+    path: PathBuf,
+    compression: &str,
+    row_group_size: usize,
+) -> PyResult<usize> {
+    if row_group_size == 0 {
+        return Err(PyValueError::new_err("row_group_size must be positive"));
+    }
+    let compression = parquet::compression(compression)?;
+    let mut messages = stream.start()?;
+
+    parsers::without_gil(|| {
+        let mut sink = parquet::ParquetSink::create(&path, compression)?;
+        let mut written = 0;
+        loop {
+            let row_group = messages.next_batch(row_group_size)?;
+            if row_group.is_empty() {
+                break;
+            }
+            sink.write(&row_group)?;
+            written += row_group.len();
+        }
+        sink.close()?;
+        Ok(written)
+    })
 }
@@ -0,0 +1,231 @@
+// Line-by-line parsing of the line-based formats (WhatsApp TXT, Discord JSONL) from any
+// reader, so a zipped chat is parsed while it is decompressed and never held in memory
+// as a whole. Upstream has the same iterators, but they only open files on disk
+//
+// WhatsApp follows the upstream parser line for line: the date format is detected on the
+// first lines, system messages are dropped and continuation lines join the previous message
+
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::types::PyPlatform;
+use chatpack::parsing::discord::{parse_discord_stream_message, DiscordStreamMessage};
+use chatpack::parsing::whatsapp::{
+    detect_whatsapp_format_owned, is_whatsapp_system_message, parse_whatsapp_timestamp, DateFormat,
+};
+use chatpack::{ChatpackError, Message};
+use regex::Regex;
+use std::io::BufRead;
+
+/// Lines the WhatsApp date format is detected on, as upstream
+const FORMAT_SAMPLE_LINES: usize = 20;
+
+pub type MessageResults<'a> = Box<dyn Iterator<Item = Result<Message, ErrorInfo>> + 'a>;
+
+/// Can a chat of this platform be parsed line by line, judging by its first bytes
+pub fn supports(platform: PyPlatform, head: &[u8]) -> bool {
+    match platform {
+        PyPlatform::WhatsApp => true,
+        PyPlatform::Discord => is_jsonl(head),
+        PyPlatform::Telegram | PyPlatform::Instagram => false,
+    }
+}
+
+// Helper: JSONL when the first line is a whole Discord message
+fn is_jsonl(head: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(head);
+    let first = text.trim_start_matches('\u{feff}').lines().next();
+    first.is_some_and(|line| serde_json::from_str::<DiscordStreamMessage>(line.trim()).is_ok())
+}
+
+/// Messages of a line-based chat, read as they are needed
+pub fn messages<'a>(
+    platform: PyPlatform,
+    reader: impl BufRead + 'a,
+) -> Result<MessageResults<'a>, ErrorInfo> {
+    let lines = Lines {
+        reader,
+        buffer: String::new(),
+        number: 0,
+    };
+    match platform {
+        PyPlatform::WhatsApp => Ok(Box::new(WhatsAppLines::new(lines)?)),
+        PyPlatform::Discord => Ok(Box::new(JsonlLines { lines })),
+        other => Err(ErrorInfo::new(
+            ErrorKind::UnsupportedFormat,
+            format!("{} exports can't be parsed line by line", other.as_str()),
+        )
+        .with_platform(other)),
+    }
+}
+
+// Lines without their line break, numbered from 1 for errors
+struct Lines<R> {
+    reader: R,
+    buffer: String,
+    number: u64,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn next_line(&mut self) -> Result<Option<&str>, ErrorInfo> {
+        self.buffer.clear();
+        let read = match self.reader.read_line(&mut self.buffer) {
+            Ok(read) => read,
+            Err(e) => {
+                let kind = match e.kind() {
+                    std::io::ErrorKind::InvalidData => ErrorKind::Parse,
+                    _ => ErrorKind::Io,
+                };
+                let mut info = ErrorInfo::new(kind, e.to_string());
+                info.line = Some(self.number + 1);
+                return Err(info);
+            }
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+
+        self.number += 1;
+        let line = self.buffer.strip_suffix('\n').unwrap_or(&self.buffer);
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line)))
+    }
+}
+
+struct WhatsAppLines<R> {
+    lines: Lines<R>,
+    /// Lines read for format detection, parsed first
+    sample: std::vec::IntoIter<String>,
+    /// None for an empty chat
+    format: Option<(DateFormat, Regex)>,
+    /// Message still collecting continuation lines
+    pending: Option<Message>,
+    finished: bool,
+}
+
+impl<R: BufRead> WhatsAppLines<R> {
+    fn new(mut lines: Lines<R>) -> Result<Self, ErrorInfo> {
+        let mut sample = Vec::new();
+        while sample.len() < FORMAT_SAMPLE_LINES {
+            match lines.next_line()? {
+                Some(line) => sample.push(line.to_string()),
+                None => break,
+            }
+        }
+
+        let format = if sample.is_empty() {
+            None
+        } else {
+            let format = detect_whatsapp_format_owned(&sample).ok_or_else(|| {
+                ChatpackError::invalid_format(
+                    "WhatsApp",
+                    "Could not detect WhatsApp export format. \
+                     Make sure the file is a valid WhatsApp chat export.",
+                )
+            })?;
+            let regex = Regex::new(format.pattern())
+                .map_err(|e| ChatpackError::invalid_format("WhatsApp", e.to_string()))?;
+            Some((format, regex))
+        };
+
+        Ok(WhatsAppLines {
+            lines,
+            sample: sample.into_iter(),
+            format,
+            pending: None,
+            finished: false,
+        })
+    }
+}
+
+// Helper: take in one line, returning the message it completes
+fn whatsapp_line(
+    format: DateFormat,
+    regex: &Regex,
+    pending: &mut Option<Message>,
+    line: &str,
+) -> Option<Message> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let Some(caps) = regex.captures(line) else {
+        // Continuation line, orphans before the first message are dropped
+        if let Some(msg) = pending {
+            msg.content.push('\n');
+            msg.content.push_str(line);
+        }
+        return None;
+    };
+
+    let field = |i| caps.get(i).map_or("", |m| m.as_str());
+    let (sender, content) = (field(3).trim(), field(4));
+    // Lines after a system message still join the message before it
+    if is_whatsapp_system_message(sender, content) {
+        return None;
+    }
+
+    let timestamp = parse_whatsapp_timestamp(field(1), field(2), format);
+    pending.replace(Message::with_metadata(
+        sender, content, timestamp, None, None, None,
+    ))
+}
+
+impl<R: BufRead> Iterator for WhatsAppLines<R> {
+    type Item = Result<Message, ErrorInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (format, regex) = self.format.as_ref()?;
+        while !self.finished {
+            let done = match self.sample.next() {
+                Some(line) => whatsapp_line(*format, regex, &mut self.pending, &line),
+                None => match self.lines.next_line() {
+                    Ok(Some(line)) => whatsapp_line(*format, regex, &mut self.pending, line),
+                    Ok(None) => {
+                        self.finished = true;
+                        self.pending.take()
+                    }
+                    Err(e) => {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                },
+            };
+            if let Some(msg) = done {
+                return Some(Ok(msg));
+            }
+        }
+        None
+    }
+}
+
+struct JsonlLines<R> {
+    lines: Lines<R>,
+}
+
+impl<R: BufRead> Iterator for JsonlLines<R> {
+    type Item = Result<Message, ErrorInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let raw: DiscordStreamMessage = match serde_json::from_str(line) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    let mut info = ErrorInfo::from(ChatpackError::from(e));
+                    info.line = Some(self.lines.number);
+                    return Some(Err(info));
+                }
+            };
+            // Messages without text are skipped, as upstream
+            if let Some(msg) = parse_discord_stream_message(&raw) {
+                return Some(Ok(msg));
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Parsing many files at once on a pool of worker threads
+
+use crate::detect;
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::parsers::{self, Pipeline};
+use crate::types::{PyMessage, PyPlatform};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub type FileResult = Result<Vec<PyMessage>, ErrorInfo>;
+
+/// Number of worker threads when none is requested
+pub fn default_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Parse every file, returning the results in input order.
+/// `platform` = None detects the platform of each file separately
+pub fn parse_files(
+    paths: &[PathBuf],
+    platform: Option<PyPlatform>,
+    pipeline: &Pipeline,
+    workers: usize,
+) -> Vec<FileResult> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<FileResult>> = paths.iter().map(|_| None).collect();
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.clamp(1, paths.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(i) else {
+                            break;
+                        };
+                        done.push((i, parse_file(path, platform, pipeline)));
+                    }
+                    done
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let done = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (i, result) in done {
+                results[i] = Some(result);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every path is handled by a worker"))
+        .collect()
+}
+
+fn parse_file(path: &Path, platform: Option<PyPlatform>, pipeline: &Pipeline) -> FileResult {
+    let platform = match platform {
+        Some(platform) => platform,
+        None => {
+            detect::detect_path(path)?
+                .ok_or_else(|| {
+                    ErrorInfo::new(
+                        ErrorKind::UnsupportedFormat,
+                        "Could not detect the chat platform of the export",
+                    )
+                    .with_path(path)
+                })?
+                .platform
+        }
+    };
+
+    parsers::parse_path_with(parsers::upstream_parser(platform).as_ref(), pipeline, path)
+        .map(parsers::Parsed::into_messages)
+}
+
+/// Concatenate per-file messages in chronological order.
+/// The sort is stable, messages without timestamps go last
+pub fn chronological(files: impl IntoIterator<Item = Vec<PyMessage>>) -> Vec<PyMessage> {
+    let mut messages: Vec<PyMessage> = files.into_iter().flatten().collect();
+    messages.sort_by_key(|m| (m.timestamp.is_none(), m.timestamp));
+    messages
+}
@@ -0,0 +1,92 @@
+// Parquet output through the Arrow writer, with the columns of arrow.rs
+//
+// Every write() call becomes one row group, so streams are written
+// incrementally without holding the whole export in memory
+
+use crate::arrow;
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::types::PyMessage;
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use ::parquet::errors::ParquetError;
+use ::parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Parse a compression codec name
+pub fn compression(name: &str) -> Result<Compression, ErrorInfo> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "zstd" => Compression::ZSTD(ZstdLevel::default()),
+        "snappy" => Compression::SNAPPY,
+        "gzip" => Compression::GZIP(GzipLevel::default()),
+        "lz4" => Compression::LZ4_RAW,
+        "none" | "uncompressed" => Compression::UNCOMPRESSED,
+        other => {
+            return Err(ErrorInfo::new(
+                ErrorKind::UnsupportedFormat,
+                format!(
+                    "Unknown compression: '{}'. Expected one of: zstd, snappy, gzip, lz4, none",
+                    other
+                ),
+            ))
+        }
+    })
+}
+
+pub struct ParquetSink {
+    writer: ArrowWriter<File>,
+    path: PathBuf,
+}
+
+impl ParquetSink {
+    pub fn create(path: &Path, compression: Compression) -> Result<Self, ErrorInfo> {
+        let file = File::create(path).map_err(|e| ErrorInfo::io(&e, path))?;
+        let properties = WriterProperties::builder()
+            .set_compression(compression)
+            .build();
+        let writer = ArrowWriter::try_new(file, arrow::schema(), Some(properties))
+            .map_err(|e| parquet_error(e, path))?;
+
+        Ok(ParquetSink {
+            writer,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Write the messages as one row group
+    pub fn write(&mut self, messages: &[PyMessage]) -> Result<(), ErrorInfo> {
+        let batch = arrow::build(messages)?;
+        self.writer
+            .write(&batch)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| parquet_error(e, &self.path))
+    }
+
+    /// Write the footer, the file is unreadable without it
+    pub fn close(self) -> Result<(), ErrorInfo> {
+        self.writer
+            .close()
+            .map(|_| ())
+            .map_err(|e| parquet_error(e, &self.path))
+    }
+}
+
+/// Write messages to a Parquet file
+pub fn write(
+    messages: &[PyMessage],
+    path: &Path,
+    compression: Compression,
+) -> Result<(), ErrorInfo> {
+    let mut sink = ParquetSink::create(path, compression)?;
+    sink.write(messages)?;
+    sink.close()
+}
+
+// Helper: writer failures are I/O errors unless the data itself is rejected
+fn parquet_error(err: ParquetError, path: &Path) -> ErrorInfo {
+    let kind = match err {
+        ParquetError::External(_) => ErrorKind::Io,
+        _ => ErrorKind::Base,
+    };
+    ErrorInfo::new(kind, err.to_string()).with_path(path)
+}
@@ -1,289 +1,444 @@
 #![allow(clippy::useless_conversion)]
-use crate::types::PyMessage;
+use crate::archive;
+use crate::conversion;
+use crate::errors::ErrorInfo;
+use crate::instagram;
+use crate::processing::{self, LengthFilter, MergeOptions, Merger};
+use crate::redact::{self, PyRedactor, RedactArg};
+use crate::source::{decode_utf8, InputSource};
+use crate::types::{MergeArg, PyFilterConfig, PyMessage, PyOutputConfig, PyPlatform};
 use chatpack::parser::Parser;
-use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 use std::path::Path;
 
 // Helper function to create filter config from parameters
 fn build_filter_config(
-    _min_length: Option<usize>, // Не используем здесь, фильтруем вручную
+    mut config: chatpack::core::filter::FilterConfig,
     date_from: Option<String>,
     date_to: Option<String>,
 ) -> PyResult<chatpack::core::filter::FilterConfig> {
-    let mut config = chatpack::core::filter::FilterConfig::new();
-
     if let Some(date) = date_from {
-        config = config
-            .with_date_from(&date)
-            .map_err(|e| PyValueError::new_err(format!("Invalid start date: {}", e)))?;
+        config = config.with_date_from(&date).map_err(ErrorInfo::from)?;
     }
 
     if let Some(date) = date_to {
-        config = config
-            .with_date_to(&date)
-            .map_err(|e| PyValueError::new_err(format!("Invalid end date: {}", e)))?;
+        config = config.with_date_to(&date).map_err(ErrorInfo::from)?;
     }
 
     Ok(config)
 }
 
-// Helper: ручная фильтрация длины
-fn filter_by_length(
+// Helper function to apply merge if needed
+fn maybe_merge(
     messages: Vec<chatpack::Message>,
-    min_length: Option<usize>,
+    merge: Option<&MergeOptions>,
 ) -> Vec<chatpack::Message> {
-    if let Some(min) = min_length {
-        messages
-            .into_iter()
-            .filter(|m| m.content.chars().count() >= min)
-            .collect()
+    if let Some(options) = merge {
+        processing::merge_consecutive(messages, options)
     } else {
         messages
     }
 }
 
-// Helper function to apply merge if needed
-fn maybe_merge(messages: Vec<chatpack::Message>, merge: bool) -> Vec<chatpack::Message> {
-    if merge {
-        chatpack::prelude::merge_consecutive(messages)
-    } else {
-        messages
-    }
-}
-
-pub fn parse_telegram_impl(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    let parser = chatpack::parsers::TelegramParser::new();
-
-    let messages = parser
-        .parse(Path::new(&path))
-        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
-
-    let filter_config = build_filter_config(min_length, date_from, date_to)?;
-    let filtered_by_config = chatpack::core::filter::apply_filters(messages, &filter_config);
-    let filtered = filter_by_length(filtered_by_config, min_length);
-
-    let result = maybe_merge(filtered, merge);
-
-    Ok(result.into_iter().map(PyMessage::from_rust).collect())
-}
-
-pub fn parse_whatsapp_impl(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    let parser = chatpack::parsers::WhatsAppParser::new();
-
-    let messages = parser
-        .parse(Path::new(&path))
-        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
-
-    let filter_config = build_filter_config(min_length, date_from, date_to)?;
-    let filtered_by_config = chatpack::core::filter::apply_filters(messages, &filter_config);
-    let filtered = filter_by_length(filtered_by_config, min_length);
-
-    let result = maybe_merge(filtered, merge);
-
-    Ok(result.into_iter().map(PyMessage::from_rust).collect())
-}
-
-pub fn parse_instagram_impl(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    let parser = chatpack::parsers::InstagramParser::new();
-
-    let messages = parser
-        .parse(Path::new(&path))
-        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
-
-    let filter_config = build_filter_config(min_length, date_from, date_to)?;
-    let filtered_by_config = chatpack::core::filter::apply_filters(messages, &filter_config);
-    let filtered = filter_by_length(filtered_by_config, min_length);
-
-    let result = maybe_merge(filtered, merge);
-
-    Ok(result.into_iter().map(PyMessage::from_rust).collect())
+/// Post-parse processing: sender/date filters, length limits and merging
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    filter: chatpack::core::filter::FilterConfig,
+    length: LengthFilter,
+    merge: Option<MergeOptions>,
 }
 
-pub fn parse_discord_impl(
-    path: String,
-    merge: bool,
-    min_length: Option<usize>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-) -> PyResult<Vec<PyMessage>> {
-    let parser = chatpack::parsers::DiscordParser::new();
-
-    let messages = parser
-        .parse(Path::new(&path))
-        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+impl Pipeline {
+    /// Build from the keyword arguments of the parse_* functions
+    pub fn from_args(
+        merge: Option<MergeOptions>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        date_from: Option<String>,
+        date_to: Option<String>,
+    ) -> PyResult<Self> {
+        Ok(Pipeline {
+            filter: build_filter_config(Default::default(), date_from, date_to)?,
+            length: LengthFilter {
+                min_length,
+                max_length,
+                truncate: false,
+            },
+            merge,
+        })
+    }
 
-    let filter_config = build_filter_config(min_length, date_from, date_to)?;
-    let filtered_by_config = chatpack::core::filter::apply_filters(messages, &filter_config);
-    let filtered = filter_by_length(filtered_by_config, min_length);
+    /// Build from a FilterConfig and merge settings
+    pub fn from_config(
+        filter: Option<PyFilterConfig>,
+        merge: Option<MergeOptions>,
+    ) -> PyResult<Self> {
+        let Some(filter) = filter else {
+            return Ok(Pipeline {
+                merge,
+                ..Default::default()
+            });
+        };
+
+        Ok(Pipeline {
+            length: filter.length_filter(),
+            filter: filter.into_rust()?,
+            merge,
+        })
+    }
 
-    let result = maybe_merge(filtered, merge);
+    /// Copy with per-call arguments taking precedence over the stored settings
+    pub fn with_overrides(
+        &self,
+        merge: Option<MergeArg>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        date_from: Option<String>,
+        date_to: Option<String>,
+    ) -> PyResult<Self> {
+        let mut pipeline = self.clone();
 
-    Ok(result.into_iter().map(PyMessage::from_rust).collect())
-}
+        pipeline.filter = build_filter_config(pipeline.filter, date_from, date_to)?;
+        if min_length.is_some() {
+            pipeline.length.min_length = min_length;
+        }
+        if max_length.is_some() {
+            pipeline.length.max_length = max_length;
+        }
+        if merge.is_some() {
+            pipeline.merge = MergeArg::resolve(merge);
+        }
 
-/// Telegram Parser class
-#[pyclass]
-pub struct TelegramParser {
-    parser: chatpack::parsers::TelegramParser,
-}
+        Ok(pipeline)
+    }
 
-#[pymethods]
-impl TelegramParser {
-    #[new]
-    fn new() -> Self {
-        TelegramParser {
-            parser: chatpack::parsers::TelegramParser::new(),
+    /// Filter a single message, the streaming counterpart of run() without merging
+    pub fn filter_one(&self, msg: chatpack::Message) -> Option<chatpack::Message> {
+        if !processing::matches_filter(&msg, &self.filter) {
+            return None;
         }
+        self.length.apply_one(msg)
+    }
+
+    /// Incremental merger for streaming, if merging is enabled
+    pub fn merger(&self) -> Option<Merger> {
+        self.merge.clone().map(Merger::new)
     }
 
-    #[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-    fn parse(
+    /// Counterpart of run() for messages read one by one: filtered and merged as
+    /// they come, so only the kept messages are held
+    pub fn run_each(
         &self,
-        path: String,
-        merge: bool,
-        min_length: Option<usize>,
-        date_from: Option<String>,
-        date_to: Option<String>,
-    ) -> PyResult<Vec<PyMessage>> {
-        parse_telegram_impl(path, merge, min_length, date_from, date_to)
+        messages: impl Iterator<Item = Result<chatpack::Message, ErrorInfo>>,
+    ) -> Result<Vec<chatpack::Message>, ErrorInfo> {
+        let mut merger = self.merger();
+        let mut result = Vec::new();
+        for msg in messages {
+            let Some(msg) = self.filter_one(msg?) else {
+                continue;
+            };
+            match &mut merger {
+                Some(merger) => result.extend(merger.push(msg)),
+                None => result.push(msg),
+            }
+        }
+        result.extend(merger.as_mut().and_then(Merger::finish));
+        Ok(result)
     }
 
-    fn parse_str(&self, content: String) -> PyResult<Vec<PyMessage>> {
-        let messages = self
-            .parser
-            .parse_str(&content)
-            .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    pub fn run(&self, messages: Vec<chatpack::Message>) -> Vec<chatpack::Message> {
+        let filtered_by_config = chatpack::core::filter::apply_filters(messages, &self.filter);
+        let filtered = self.length.apply(filtered_by_config);
 
-        Ok(messages.into_iter().map(PyMessage::from_rust).collect())
+        maybe_merge(filtered, self.merge.as_ref())
     }
 }
 
-/// WhatsApp Parser class
-#[pyclass]
-pub struct WhatsAppParser {
-    parser: chatpack::parsers::WhatsAppParser,
+/// Parsed messages of one platform before any Python object is made, with the
+/// conversation title of each run of messages (only Instagram has titles)
+pub struct Parsed {
+    pub platform: PyPlatform,
+    pub messages: Vec<chatpack::Message>,
+    /// (index of the first message, title) per conversation
+    conversations: Vec<(usize, Option<String>)>,
 }
 
-#[pymethods]
-impl WhatsAppParser {
-    #[new]
-    fn new() -> Self {
-        WhatsAppParser {
-            parser: chatpack::parsers::WhatsAppParser::new(),
+impl Parsed {
+    pub fn new(platform: PyPlatform) -> Self {
+        Parsed {
+            platform,
+            messages: Vec::new(),
+            conversations: Vec::new(),
         }
     }
 
-    #[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-    fn parse(
-        &self,
-        path: String,
-        merge: bool,
-        min_length: Option<usize>,
-        date_from: Option<String>,
-        date_to: Option<String>,
-    ) -> PyResult<Vec<PyMessage>> {
-        parse_whatsapp_impl(path, merge, min_length, date_from, date_to)
+    /// Append the messages of one conversation
+    pub fn push(&mut self, messages: Vec<chatpack::Message>, title: Option<String>) {
+        self.conversations.push((self.messages.len(), title));
+        self.messages.extend(messages);
     }
 
-    fn parse_str(&self, content: String) -> PyResult<Vec<PyMessage>> {
-        let messages = self
-            .parser
-            .parse_str(&content)
-            .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    /// Conversation title of every message, in order
+    pub fn titles(&self) -> impl Iterator<Item = Option<&str>> + Clone + '_ {
+        let ends = self
+            .conversations
+            .iter()
+            .skip(1)
+            .map(|(start, _)| *start)
+            .chain([self.messages.len()]);
+        self.conversations
+            .iter()
+            .zip(ends)
+            .flat_map(|((start, title), end)| std::iter::repeat_n(title.as_deref(), end - start))
+    }
 
-        Ok(messages.into_iter().map(PyMessage::from_rust).collect())
+    /// Conversation titles, each once
+    pub fn titles_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.conversations
+            .iter_mut()
+            .filter_map(|(_, title)| title.as_mut())
+    }
+
+    pub fn into_messages(self) -> Vec<PyMessage> {
+        let titles: Vec<Option<String>> = self.titles().map(|t| t.map(str::to_string)).collect();
+        let platform = Some(self.platform);
+        self.messages
+            .into_iter()
+            .zip(titles)
+            .map(|(msg, conversation)| PyMessage {
+                conversation,
+                ..PyMessage::from_rust(msg, platform)
+            })
+            .collect()
     }
 }
 
-/// Instagram Parser class
-#[pyclass]
-pub struct InstagramParser {
-    parser: chatpack::parsers::InstagramParser,
+/// Run Rust-only work with the GIL released, converting errors afterwards
+pub fn without_gil<R: Send>(f: impl FnOnce() -> Result<R, ErrorInfo> + Send) -> PyResult<R> {
+    Python::with_gil(|py| py.allow_threads(f)).map_err(Into::into)
 }
 
-#[pymethods]
-impl InstagramParser {
-    #[new]
-    fn new() -> Self {
-        InstagramParser {
-            parser: chatpack::parsers::InstagramParser::new(),
-        }
-    }
+// Helper: parse a path or in-memory source with an upstream parser and run the pipeline
+fn parse_source_with(
+    parser: &dyn Parser,
+    pipeline: &Pipeline,
+    source: &InputSource<'_>,
+) -> PyResult<Parsed> {
+    let Some(path) = source.path() else {
+        return source.with_bytes(|data| {
+            without_gil(|| {
+                if archive::is_zip(data) {
+                    return archive::open_bytes(data)?.parse(parser, pipeline);
+                }
+                parse_content_with(parser, pipeline, decode_utf8(data)?)
+            })
+        });
+    };
+
+    without_gil(|| parse_path_with(parser, pipeline, path))
+}
 
-    #[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-    fn parse(
-        &self,
-        path: String,
-        merge: bool,
-        min_length: Option<usize>,
-        date_from: Option<String>,
-        date_to: Option<String>,
-    ) -> PyResult<Vec<PyMessage>> {
-        parse_instagram_impl(path, merge, min_length, date_from, date_to)
+// Helper: parse a file, a zipped export or an Instagram conversation directory
+// and run the pipeline
+pub fn parse_path_with(
+    parser: &dyn Parser,
+    pipeline: &Pipeline,
+    path: &Path,
+) -> Result<Parsed, ErrorInfo> {
+    let platform = PyPlatform::try_from(parser.platform())?;
+    if platform == PyPlatform::Instagram && path.is_dir() {
+        return instagram::parse_directory(parser, pipeline, path);
+    }
+    if archive::is_zip_file(path) {
+        return archive::open_path(path)?.parse(parser, pipeline);
     }
 
-    fn parse_str(&self, content: String) -> PyResult<Vec<PyMessage>> {
-        let messages = self
-            .parser
-            .parse_str(&content)
-            .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    let messages = parser
+        .parse(path)
+        .map_err(|e| ErrorInfo::from(e).with_path(path).with_platform(platform))?;
 
-        Ok(messages.into_iter().map(PyMessage::from_rust).collect())
+    let mut parsed = Parsed::new(platform);
+    parsed.push(pipeline.run(messages), None);
+    Ok(parsed)
+}
+
+/// Upstream parser for a platform
+pub fn upstream_parser(platform: PyPlatform) -> Box<dyn Parser> {
+    match platform {
+        PyPlatform::Telegram => Box::new(chatpack::parsers::TelegramParser::new()),
+        PyPlatform::WhatsApp => Box::new(chatpack::parsers::WhatsAppParser::new()),
+        PyPlatform::Instagram => Box::new(chatpack::parsers::InstagramParser::new()),
+        PyPlatform::Discord => Box::new(chatpack::parsers::DiscordParser::new()),
     }
 }
 
-/// Discord Parser class
-#[pyclass]
-pub struct DiscordParser {
-    parser: chatpack::parsers::DiscordParser,
+// Helper: parse in-memory content with an upstream parser and run the pipeline
+fn parse_content_with(
+    parser: &dyn Parser,
+    pipeline: &Pipeline,
+    content: &str,
+) -> Result<Parsed, ErrorInfo> {
+    let platform = PyPlatform::try_from(parser.platform())?;
+    let messages = parser
+        .parse_str(content)
+        .map_err(|e| ErrorInfo::from(e).with_platform(platform))?;
+
+    let mut parsed = Parsed::new(platform);
+    parsed.push(pipeline.run(messages), None);
+    Ok(parsed)
 }
 
-#[pymethods]
-impl DiscordParser {
-    #[new]
-    fn new() -> Self {
-        DiscordParser {
-            parser: chatpack::parsers::DiscordParser::new(),
-        }
-    }
+/// Parse a source of the given platform, with the keyword arguments of the parse_* functions
+pub fn parse_platform_impl(
+    platform: PyPlatform,
+    path: InputSource<'_>,
+    merge: Option<MergeOptions>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+) -> PyResult<Parsed> {
+    let pipeline = Pipeline::from_args(merge, min_length, max_length, date_from, date_to)?;
 
-    #[pyo3(signature = (path, merge=false, min_length=None, date_from=None, date_to=None))]
-    fn parse(
-        &self,
-        path: String,
-        merge: bool,
-        min_length: Option<usize>,
-        date_from: Option<String>,
-        date_to: Option<String>,
-    ) -> PyResult<Vec<PyMessage>> {
-        parse_discord_impl(path, merge, min_length, date_from, date_to)
+    parse_source_with(upstream_parser(platform).as_ref(), &pipeline, &path)
+}
+
+// Helper: a per-call redact argument overrides the parser's redactor
+fn redactor_override(
+    default: &Option<PyRedactor>,
+    redact: Option<RedactArg>,
+) -> PyResult<Option<PyRedactor>> {
+    match redact {
+        Some(arg) => RedactArg::resolve(Some(arg)),
+        None => Ok(default.clone()),
     }
+}
 
-    fn parse_str(&self, content: String) -> PyResult<Vec<PyMessage>> {
-        let messages = self
-            .parser
-            .parse_str(&content)
-            .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+// The parser classes differ only in the upstream parser they wrap
+macro_rules! parser_class {
+    ($(#[$meta:meta])* $name:ident, $upstream:ty) => {
+        $(#[$meta])*
+        #[pyclass]
+        pub struct $name {
+            parser: $upstream,
+            pipeline: Pipeline,
+            output: PyOutputConfig,
+            redactor: Option<PyRedactor>,
+        }
 
-        Ok(messages.into_iter().map(PyMessage::from_rust).collect())
-    }
+        #[pymethods]
+        impl $name {
+            #[new]
+            #[pyo3(signature = (filter=None, merge=None, output=None, redact=None))]
+            fn new(
+                filter: Option<PyFilterConfig>,
+                merge: Option<MergeArg>,
+                output: Option<PyOutputConfig>,
+                redact: Option<RedactArg>,
+            ) -> PyResult<Self> {
+                Ok($name {
+                    parser: <$upstream>::new(),
+                    pipeline: Pipeline::from_config(filter, MergeArg::resolve(merge))?,
+                    output: output.unwrap_or_default(),
+                    redactor: RedactArg::resolve(redact)?,
+                })
+            }
+
+            #[pyo3(signature = (path, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn parse(
+                &self,
+                path: InputSource<'_>,
+                merge: Option<MergeArg>,
+                min_length: Option<usize>,
+                max_length: Option<usize>,
+                date_from: Option<String>,
+                date_to: Option<String>,
+                redact: Option<RedactArg>,
+            ) -> PyResult<Vec<PyMessage>> {
+                let pipeline = self
+                    .pipeline
+                    .with_overrides(merge, min_length, max_length, date_from, date_to)?;
+                let redactor = redactor_override(&self.redactor, redact)?;
+                let parsed = parse_source_with(&self.parser, &pipeline, &path)?;
+                Ok(redact::apply_parsed(redactor.as_ref(), parsed).into_messages())
+            }
+
+            #[pyo3(signature = (content, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn parse_str(
+                &self,
+                content: String,
+                merge: Option<MergeArg>,
+                min_length: Option<usize>,
+                max_length: Option<usize>,
+                date_from: Option<String>,
+                date_to: Option<String>,
+                redact: Option<RedactArg>,
+            ) -> PyResult<Vec<PyMessage>> {
+                let pipeline = self
+                    .pipeline
+                    .with_overrides(merge, min_length, max_length, date_from, date_to)?;
+                let redactor = redactor_override(&self.redactor, redact)?;
+                let parsed = without_gil(|| parse_content_with(&self.parser, &pipeline, &content))?;
+                Ok(redact::apply_parsed(redactor.as_ref(), parsed).into_messages())
+            }
+
+            #[pyo3(signature = (data, merge=None, min_length=None, max_length=None, date_from=None, date_to=None, redact=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn parse_bytes(
+                &self,
+                data: Bound<'_, PyBytes>,
+                merge: Option<MergeArg>,
+                min_length: Option<usize>,
+                max_length: Option<usize>,
+                date_from: Option<String>,
+                date_to: Option<String>,
+                redact: Option<RedactArg>,
+            ) -> PyResult<Vec<PyMessage>> {
+                let pipeline = self
+                    .pipeline
+                    .with_overrides(merge, min_length, max_length, date_from, date_to)?;
+                let redactor = redactor_override(&self.redactor, redact)?;
+                // Same path as parse(): zipped exports are detected here too
+                let parsed = parse_source_with(&self.parser, &pipeline, &InputSource::Bytes(data))?;
+                Ok(redact::apply_parsed(redactor.as_ref(), parsed).into_messages())
+            }
+
+            /// Render messages using the parser's output config
+            #[pyo3(signature = (messages, format="csv"))]
+            fn render(&self, messages: Vec<PyMessage>, format: &str) -> PyResult<String> {
+                conversion::render_messages(&messages, format, &self.output)
+            }
+
+            /// Write messages to a file using the parser's output config
+            #[pyo3(signature = (messages, path, format="csv"))]
+            fn write(&self, messages: Vec<PyMessage>, path: String, format: &str) -> PyResult<()> {
+                let data = conversion::render_messages(&messages, format, &self.output)?;
+                conversion::write_output(&path, &data)
+            }
+        }
+    };
 }
+
+parser_class!(
+    /// Telegram Parser class
+    TelegramParser,
+    chatpack::parsers::TelegramParser
+);
+parser_class!(
+    /// WhatsApp Parser class
+    WhatsAppParser,
+    chatpack::parsers::WhatsAppParser
+);
+parser_class!(
+    /// Instagram Parser class
+    InstagramParser,
+    chatpack::parsers::InstagramParser
+);
+parser_class!(
+    /// Discord Parser class
+    DiscordParser,
+    chatpack::parsers::DiscordParser
+);
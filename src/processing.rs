@@ -0,0 +1,210 @@
+// Message processing that chatpack 0.5 does not provide out of the box
+// (upstream merge_consecutive has no time threshold, length cap or separator)
+
+use chatpack::core::filter::FilterConfig;
+use chrono::{DateTime, Utc};
+
+/// Options for merging consecutive messages from the same sender
+#[derive(Clone, Debug)]
+pub struct MergeOptions {
+    /// Maximum gap in seconds between neighbouring messages, None = no limit
+    pub time_threshold: Option<i64>,
+    /// Maximum length (in chars) of a merged message, None = no limit
+    pub max_merged_length: Option<usize>,
+    /// Separator inserted between merged contents
+    pub separator: String,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            time_threshold: Some(300),
+            max_merged_length: None,
+            separator: "\n".to_string(),
+        }
+    }
+}
+
+/// Incremental merger: feed messages one by one, get merged messages back
+/// as soon as a run of same-sender messages ends
+pub struct Merger {
+    options: MergeOptions,
+    current: Option<chatpack::Message>,
+    current_len: usize,
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl Merger {
+    pub fn new(options: MergeOptions) -> Self {
+        Merger {
+            options,
+            current: None,
+            current_len: 0,
+            last_timestamp: None,
+        }
+    }
+
+    /// Add a message, returning the previous merged message if the run ended
+    pub fn push(&mut self, msg: chatpack::Message) -> Option<chatpack::Message> {
+        let msg_len = msg.content.chars().count();
+
+        if self.can_merge(&msg, msg_len) {
+            let current = self.current.as_mut().expect("checked by can_merge");
+            current.content.push_str(&self.options.separator);
+            current.content.push_str(&msg.content);
+            self.current_len += self.options.separator.chars().count() + msg_len;
+            if msg.timestamp.is_some() {
+                self.last_timestamp = msg.timestamp;
+            }
+            return None;
+        }
+
+        self.current_len = msg_len;
+        self.last_timestamp = msg.timestamp;
+        self.current.replace(msg)
+    }
+
+    /// Flush the last pending merged message
+    pub fn finish(&mut self) -> Option<chatpack::Message> {
+        self.current_len = 0;
+        self.last_timestamp = None;
+        self.current.take()
+    }
+
+    fn can_merge(&self, msg: &chatpack::Message, msg_len: usize) -> bool {
+        let Some(current) = &self.current else {
+            return false;
+        };
+
+        if current.sender != msg.sender {
+            return false;
+        }
+
+        // Messages without timestamps can't be checked, so they merge freely
+        if let (Some(threshold), Some(last), Some(ts)) = (
+            self.options.time_threshold,
+            self.last_timestamp,
+            msg.timestamp,
+        ) {
+            if (ts - last).num_seconds().abs() > threshold {
+                return false;
+            }
+        }
+
+        if let Some(max_len) = self.options.max_merged_length {
+            let merged_len = self.current_len + self.options.separator.chars().count() + msg_len;
+            if merged_len > max_len {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Merge consecutive messages from the same sender
+pub fn merge_consecutive(
+    messages: Vec<chatpack::Message>,
+    options: &MergeOptions,
+) -> Vec<chatpack::Message> {
+    let mut merger = Merger::new(options.clone());
+    let mut merged = Vec::with_capacity(messages.len());
+
+    for msg in messages {
+        if let Some(done) = merger.push(msg) {
+            merged.push(done);
+        }
+    }
+    merged.extend(merger.finish());
+
+    merged.shrink_to_fit();
+    merged
+}
+
+/// Check a single message against upstream sender/date filters
+/// (same rules as chatpack::core::filter::apply_filters, for streaming)
+pub fn matches_filter(msg: &chatpack::Message, config: &FilterConfig) -> bool {
+    if let Some(from) = &config.from {
+        if !msg.sender.eq_ignore_ascii_case(from) {
+            return false;
+        }
+    }
+
+    // Messages without timestamps are excluded once a date filter is set
+    if config.has_date_filter() {
+        let Some(ts) = msg.timestamp else {
+            return false;
+        };
+        if config.after.is_some_and(|after| ts < after) {
+            return false;
+        }
+        if config.before.is_some_and(|before| ts > before) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Length limits, applied on top of upstream sender/date filters
+/// (chatpack 0.5 FilterConfig has no length options)
+#[derive(Clone, Debug, Default)]
+pub struct LengthFilter {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// Trim messages longer than max_length instead of dropping them
+    pub truncate: bool,
+}
+
+impl LengthFilter {
+    pub fn is_active(&self) -> bool {
+        self.min_length.is_some() || self.max_length.is_some()
+    }
+
+    /// Filter (or truncate) a single message
+    pub fn apply_one(&self, mut msg: chatpack::Message) -> Option<chatpack::Message> {
+        let len = msg.content.chars().count();
+
+        if self.min_length.is_some_and(|min| len < min) {
+            return None;
+        }
+
+        if let Some(max) = self.max_length {
+            if len > max {
+                if !self.truncate {
+                    return None;
+                }
+                truncate_content(&mut msg.content, max);
+            }
+        }
+
+        Some(msg)
+    }
+
+    pub fn apply(&self, messages: Vec<chatpack::Message>) -> Vec<chatpack::Message> {
+        if !self.is_active() {
+            return messages;
+        }
+
+        messages
+            .into_iter()
+            .filter_map(|m| self.apply_one(m))
+            .collect()
+    }
+}
+
+/// Trim content to at most `max_chars` characters, ending with an ellipsis
+fn truncate_content(content: &mut String, max_chars: usize) {
+    const ELLIPSIS: char = '…';
+
+    let keep = max_chars.saturating_sub(1);
+    let cut = content
+        .char_indices()
+        .nth(keep)
+        .map_or(content.len(), |(i, _)| i);
+    content.truncate(cut);
+    content.truncate(content.trim_end().len());
+    if max_chars > 0 {
+        content.push(ELLIPSIS);
+    }
+}
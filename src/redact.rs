@@ -0,0 +1,512 @@
+// PII redaction before chat data leaves the machine
+//
+// Built-in detectors: phone numbers, emails, credit card numbers (Luhn-checked),
+// IBANs (mod-97-checked), URLs carrying credentials or tokens, @mentions; plus
+// custom regexes. Matches are replaced by a mask, a salted hash or a typed
+// placeholder such as <PHONE_1>. Placeholders are kept per Redactor, so the same
+// value gets the same placeholder in every message (and every call) until reset()
+
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::parsers::Parsed;
+use crate::types::PyMessage;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Url,
+    Email,
+    Card,
+    Iban,
+    Phone,
+    Mention,
+    Custom,
+}
+
+/// Built-in detectors in matching order: earlier ones win overlapping matches
+const BUILTIN: [(&str, Kind); 6] = [
+    ("url", Kind::Url),
+    ("email", Kind::Email),
+    ("card", Kind::Card),
+    ("iban", Kind::Iban),
+    ("phone", Kind::Phone),
+    ("mention", Kind::Mention),
+];
+
+fn builtin_regex(kind: Kind) -> &'static Regex {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    static CARD: OnceLock<Regex> = OnceLock::new();
+    static IBAN: OnceLock<Regex> = OnceLock::new();
+    static PHONE: OnceLock<Regex> = OnceLock::new();
+    static MENTION: OnceLock<Regex> = OnceLock::new();
+
+    let (cell, pattern) = match kind {
+        Kind::Url => (&URL, r#"(?i)\b(?:https?|ftp)://[^\s<>"']+"#),
+        Kind::Email => (
+            &EMAIL,
+            r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b",
+        ),
+        Kind::Card => (&CARD, r"\b\d(?:[ -]?\d){12,18}\b"),
+        Kind::Iban => (&IBAN, r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}\b"),
+        // +7 (912) 345-67-89, 555-123-4567, +44 20 7946 0958, ...
+        Kind::Phone => (
+            &PHONE,
+            r"(?:\+\d{1,3}[\s.-]?(?:\(\d{1,4}\)[\s.-]?)?|\(\d{1,4}\)[\s.-]?|\b)\d{2,4}(?:[\s.-]?\d{2,4}){1,4}\b",
+        ),
+        Kind::Mention => (&MENTION, r"\B@[A-Za-z0-9_](?:[A-Za-z0-9_.]*[A-Za-z0-9_])?"),
+        Kind::Custom => unreachable!("custom detectors bring their own regex"),
+    };
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+// URL query parameters and path segments that carry secrets
+fn url_secret_regex() -> &'static Regex {
+    static SECRET: OnceLock<Regex> = OnceLock::new();
+    SECRET.get_or_init(|| {
+        Regex::new(
+            r"(?ix)
+            ^[a-z]+://[^/\s@]+:[^/\s@]+@                      # user:password@host
+            | [?&\#](?:[a-z_]*token|[a-z_]*key|secret|client_secret|sig|signature
+                    |auth|password|passwd|pwd|session(?:_?id)?|code)=[^&\#\s]+
+            | /[A-Za-z0-9_-]*(?:\d[A-Za-z_-]|[A-Za-z_-]\d)[A-Za-z0-9_-]{20,}  # opaque token in the path
+            ",
+        )
+        .unwrap()
+    })
+}
+
+// Helper: Luhn checksum of a card number
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// Helper: ISO 13616 mod-97 check of an IBAN without spaces
+fn iban_checksum(iban: &str) -> bool {
+    let (head, tail) = iban.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// Validate a built-in match, returning the part to redact
+fn validate(kind: Kind, text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let matched = &text[range.clone()];
+    match kind {
+        Kind::Url => {
+            // Sentence punctuation after a link is not part of it
+            let trimmed = matched.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+            url_secret_regex()
+                .is_match(trimmed)
+                .then(|| range.start..range.start + trimmed.len())
+        }
+        Kind::Card => {
+            let digits: Vec<u32> = matched.chars().filter_map(|c| c.to_digit(10)).collect();
+            luhn(&digits).then_some(range)
+        }
+        Kind::Iban => {
+            // The match may run into a following word in capitals, keep the longest
+            // prefix that passes the checksum
+            let mut compact = String::new();
+            let mut ends = Vec::new();
+            for (i, c) in matched.char_indices() {
+                if c != ' ' {
+                    compact.push(c);
+                    ends.push(i + c.len_utf8());
+                }
+            }
+            (15..=compact.len())
+                .rev()
+                .find(|&len| iban_checksum(&compact[..len]))
+                .map(|len| range.start..range.start + ends[len - 1])
+        }
+        Kind::Phone => {
+            let digits = matched.chars().filter(char::is_ascii_digit).count();
+            let separated = matched.contains(|c: char| !c.is_ascii_digit());
+            let date = {
+                static DATE: OnceLock<Regex> = OnceLock::new();
+                DATE.get_or_init(|| Regex::new(r"^\d{1,4}[./-]\d{1,2}[./-]\d{1,4}$").unwrap())
+            };
+            // A bare run of digits is more likely an id or an amount than a phone
+            ((7..=15).contains(&digits) && (separated || digits >= 10) && !date.is_match(matched))
+                .then_some(range)
+        }
+        Kind::Email | Kind::Mention | Kind::Custom => Some(range),
+    }
+}
+
+struct Detector {
+    /// Placeholder label, e.g. PHONE
+    label: String,
+    kind: Kind,
+    regex: Regex,
+}
+
+impl Detector {
+    fn find<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.regex
+            .find_iter(text)
+            .filter_map(move |m| validate(self.kind, text, m.range()))
+            .filter(|range| !range.is_empty())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    Mask,
+    Hash,
+    Placeholder,
+}
+
+impl Strategy {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "mask" => Ok(Strategy::Mask),
+            "hash" => Ok(Strategy::Hash),
+            "placeholder" => Ok(Strategy::Placeholder),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown redaction strategy: '{}'. Expected one of: mask, hash, placeholder",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Placeholders {
+    /// (label, normalised value) -> placeholder
+    assigned: HashMap<(String, String), String>,
+    /// Next number per label
+    counters: HashMap<String, usize>,
+    /// placeholder -> first original value, to restore model output
+    originals: HashMap<String, String>,
+}
+
+pub struct Redactor {
+    detectors: Vec<Detector>,
+    strategy: Strategy,
+    /// Random per Redactor unless given, short digests of an unsalted hash are
+    /// easy to reverse for phone numbers and the like
+    salt: Vec<u8>,
+    senders: bool,
+    placeholders: Mutex<Placeholders>,
+}
+
+// Helper: the same value written differently gets the same replacement
+fn normalize(kind: Kind, value: &str) -> String {
+    match kind {
+        Kind::Phone | Kind::Card | Kind::Iban => value
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_uppercase(),
+        Kind::Email | Kind::Mention => value.to_lowercase(),
+        Kind::Url | Kind::Custom => value.to_string(),
+    }
+}
+
+impl Redactor {
+    /// Redact one text
+    pub fn redact_text(&self, text: &str) -> String {
+        // Earlier detectors win overlapping matches
+        let mut spans: Vec<(Range<usize>, &Detector)> = Vec::new();
+        for detector in &self.detectors {
+            for range in detector.find(text) {
+                let overlaps = spans
+                    .iter()
+                    .any(|(taken, _)| range.start < taken.end && taken.start < range.end);
+                if !overlaps {
+                    spans.push((range, detector));
+                }
+            }
+        }
+        if spans.is_empty() {
+            return text.to_string();
+        }
+        spans.sort_by_key(|(range, _)| range.start);
+
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, detector) in spans {
+            out.push_str(&text[last..range.start]);
+            out.push_str(&self.replacement(detector, &text[range.clone()]));
+            last = range.end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// Redact message contents (and senders and conversation titles, if enabled)
+    pub fn redact_messages(&self, messages: &mut [PyMessage]) {
+        for msg in messages {
+            self.redact_fields(&mut msg.sender, &mut msg.content);
+            if let Some(conversation) = msg.conversation.as_mut().filter(|_| self.senders) {
+                *conversation = self.redact_text(conversation);
+            }
+        }
+    }
+
+    /// Redact an upstream message, for streams
+    pub fn redact_upstream(&self, msg: &mut chatpack::Message) {
+        self.redact_fields(&mut msg.sender, &mut msg.content);
+    }
+
+    /// Redact parsed messages before they become Python objects
+    pub fn redact_parsed(&self, parsed: &mut Parsed) {
+        for msg in &mut parsed.messages {
+            self.redact_upstream(msg);
+        }
+        if self.senders {
+            for title in parsed.titles_mut() {
+                *title = self.redact_text(title);
+            }
+        }
+    }
+
+    fn redact_fields(&self, sender: &mut String, content: &mut String) {
+        *content = self.redact_text(content);
+        if self.senders {
+            *sender = self.redact_text(sender);
+        }
+    }
+
+    fn replacement(&self, detector: &Detector, value: &str) -> String {
+        match self.strategy {
+            Strategy::Mask => "*".repeat(value.chars().count()),
+            Strategy::Hash => {
+                let mut hasher = Sha256::new();
+                hasher.update(&self.salt);
+                hasher.update(normalize(detector.kind, value).as_bytes());
+                let digest = hasher.finalize();
+                let hex: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+                format!("<{}:{}>", detector.label, hex)
+            }
+            Strategy::Placeholder => {
+                let mut state = self
+                    .placeholders
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                let key = (detector.label.clone(), normalize(detector.kind, value));
+                if let Some(placeholder) = state.assigned.get(&key) {
+                    return placeholder.clone();
+                }
+
+                let counter = state.counters.entry(detector.label.clone()).or_insert(0);
+                *counter += 1;
+                let placeholder = format!("<{}_{}>", detector.label, counter);
+                state
+                    .originals
+                    .insert(placeholder.clone(), value.to_string());
+                state.assigned.insert(key, placeholder.clone());
+                placeholder
+            }
+        }
+    }
+}
+
+/// PII redactor, shared state keeps placeholders consistent across calls
+#[pyclass]
+#[derive(Clone)]
+pub struct PyRedactor {
+    pub inner: Arc<Redactor>,
+}
+
+#[pymethods]
+impl PyRedactor {
+    #[new]
+    #[pyo3(signature = (detectors=None, patterns=None, strategy="placeholder", salt=None, senders=false))]
+    fn new(
+        detectors: Option<Vec<String>>,
+        patterns: Option<&Bound<'_, PyDict>>,
+        strategy: &str,
+        salt: Option<String>,
+        senders: bool,
+    ) -> PyResult<Self> {
+        let salt = match salt {
+            Some(salt) if salt.is_empty() => {
+                return Err(PyValueError::new_err("salt must not be empty"));
+            }
+            Some(salt) => salt.into_bytes(),
+            None => {
+                let mut salt = vec![0; 16];
+                getrandom::getrandom(&mut salt).map_err(|e| {
+                    ErrorInfo::new(ErrorKind::Io, format!("Could not generate a salt: {}", e))
+                })?;
+                salt
+            }
+        };
+
+        let mut all = Vec::new();
+
+        // Custom patterns are the most specific, they go first
+        for (name, pattern) in patterns.into_iter().flat_map(|dict| dict.iter()) {
+            let (name, pattern): (String, String) = (name.extract()?, pattern.extract()?);
+            let regex = Regex::new(&pattern).map_err(|e| {
+                PyValueError::new_err(format!("Invalid pattern for '{}': {}", name, e))
+            })?;
+            all.push(Detector {
+                label: name.to_uppercase(),
+                kind: Kind::Custom,
+                regex,
+            });
+        }
+
+        let enabled: Option<Vec<String>> =
+            detectors.map(|names| names.iter().map(|n| n.to_ascii_lowercase()).collect());
+        if let Some(names) = &enabled {
+            if let Some(unknown) = names
+                .iter()
+                .find(|name| !BUILTIN.iter().any(|(builtin, _)| builtin == name))
+            {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown detector: '{}'. Expected one of: url, email, card, iban, phone, mention",
+                    unknown
+                )));
+            }
+        }
+        for (name, kind) in BUILTIN {
+            if enabled
+                .as_ref()
+                .is_some_and(|names| !names.iter().any(|n| n == name))
+            {
+                continue;
+            }
+            all.push(Detector {
+                label: name.to_uppercase(),
+                kind,
+                regex: builtin_regex(kind).clone(),
+            });
+        }
+
+        Ok(PyRedactor {
+            inner: Arc::new(Redactor {
+                detectors: all,
+                strategy: Strategy::parse(strategy)?,
+                salt,
+                senders,
+                placeholders: Mutex::default(),
+            }),
+        })
+    }
+
+    /// Redact a single string
+    fn redact_text(&self, py: Python<'_>, text: &str) -> String {
+        py.allow_threads(|| self.inner.redact_text(text))
+    }
+
+    /// Redacted copies of the messages
+    fn redact(&self, py: Python<'_>, mut messages: Vec<PyMessage>) -> Vec<PyMessage> {
+        py.allow_threads(|| self.inner.redact_messages(&mut messages));
+        messages
+    }
+
+    /// Original value of every placeholder handed out so far
+    #[getter]
+    fn mapping(&self) -> HashMap<String, String> {
+        self.lock().originals.clone()
+    }
+
+    /// Put placeholders back into a text (e.g. a model response)
+    fn restore(&self, text: &str) -> String {
+        let state = self.lock();
+        let mut out = text.to_string();
+        // Longest first, so <PHONE_1> does not clobber <PHONE_10>
+        let mut placeholders: Vec<_> = state.originals.iter().collect();
+        placeholders.sort_by_key(|(placeholder, _)| std::cmp::Reverse(placeholder.len()));
+        for (placeholder, original) in placeholders {
+            out = out.replace(placeholder.as_str(), original);
+        }
+        out
+    }
+
+    /// Forget placeholders, numbering starts again from 1
+    fn reset(&self) {
+        *self.lock() = Placeholders::default();
+    }
+
+    fn __repr__(&self) -> String {
+        let labels: Vec<&str> = self
+            .inner
+            .detectors
+            .iter()
+            .map(|d| d.label.as_str())
+            .collect();
+        let strategy = match self.inner.strategy {
+            Strategy::Mask => "mask",
+            Strategy::Hash => "hash",
+            Strategy::Placeholder => "placeholder",
+        };
+        format!("Redactor(detectors={:?}, strategy='{}')", labels, strategy)
+    }
+}
+
+impl PyRedactor {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Placeholders> {
+        self.inner
+            .placeholders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// `redact` argument accepted by the parse functions: a flag or a `Redactor`
+#[derive(FromPyObject)]
+pub enum RedactArg {
+    Flag(bool),
+    Redactor(PyRedactor),
+}
+
+impl RedactArg {
+    /// Resolve to a redactor, None when redaction is disabled
+    pub fn resolve(arg: Option<Self>) -> PyResult<Option<PyRedactor>> {
+        match arg {
+            None | Some(RedactArg::Flag(false)) => Ok(None),
+            Some(RedactArg::Flag(true)) => {
+                PyRedactor::new(None, None, "placeholder", None, false).map(Some)
+            }
+            Some(RedactArg::Redactor(redactor)) => Ok(Some(redactor)),
+        }
+    }
+}
+
+/// Redact messages if requested, with the GIL released
+pub fn apply(arg: Option<RedactArg>, mut messages: Vec<PyMessage>) -> PyResult<Vec<PyMessage>> {
+    if let Some(redactor) = RedactArg::resolve(arg)? {
+        Python::with_gil(|py| py.allow_threads(|| redactor.inner.redact_messages(&mut messages)));
+    }
+    Ok(messages)
+}
+
+/// Redact parsed messages with a resolved redactor, with the GIL released
+pub fn apply_parsed(redactor: Option<&PyRedactor>, mut parsed: Parsed) -> Parsed {
+    if let Some(redactor) = redactor {
+        Python::with_gil(|py| py.allow_threads(|| redactor.inner.redact_parsed(&mut parsed)));
+    }
+    parsed
+}
@@ -0,0 +1,159 @@
+// Conversation sessions: runs of messages without a pause longer than `gap`
+//
+// A coarser unit than merge_consecutive (which follows sender changes), meant for
+// summarisation jobs. Messages without a timestamp stay in the current session
+
+use crate::types::PyMessage;
+use chrono::{DateTime, TimeDelta, Utc};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyIterator};
+use std::collections::BTreeMap;
+
+/// A session with its summary stats
+#[pyclass]
+#[derive(Clone)]
+pub struct PySession {
+    #[pyo3(get)]
+    pub messages: Vec<PyMessage>,
+
+    #[pyo3(get)]
+    pub start_ts: Option<DateTime<Utc>>,
+
+    #[pyo3(get)]
+    pub end_ts: Option<DateTime<Utc>>,
+
+    /// Messages per sender
+    #[pyo3(get)]
+    pub participants: BTreeMap<String, usize>,
+}
+
+#[pymethods]
+impl PySession {
+    /// Time between the first and the last message
+    #[getter]
+    fn duration(&self) -> Option<TimeDelta> {
+        Some(self.end_ts? - self.start_ts?)
+    }
+
+    #[getter]
+    fn message_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    #[getter]
+    fn participant_count(&self) -> usize {
+        self.participants.len()
+    }
+
+    fn __len__(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        self.messages.clone().into_py(py).into_bound(py).iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Session(messages={}, participants={}, start_ts={}, end_ts={})",
+            self.messages.len(),
+            self.participants.len(),
+            self.start_ts
+                .map(|ts| ts.to_rfc3339())
+                .as_deref()
+                .unwrap_or("None"),
+            self.end_ts
+                .map(|ts| ts.to_rfc3339())
+                .as_deref()
+                .unwrap_or("None")
+        )
+    }
+
+    /// Summary stats as a dictionary (without the messages)
+    pub fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("start_ts", self.start_ts)?;
+        dict.set_item("end_ts", self.end_ts)?;
+        dict.set_item("duration", self.duration())?;
+        dict.set_item("message_count", self.message_count())?;
+        dict.set_item("participant_count", self.participant_count())?;
+        dict.set_item("participants", &self.participants)?;
+        Ok(dict.into())
+    }
+}
+
+impl PySession {
+    fn new(messages: Vec<PyMessage>) -> Self {
+        let mut participants = BTreeMap::new();
+        for msg in &messages {
+            *participants.entry(msg.sender.clone()).or_insert(0) += 1;
+        }
+
+        let timestamps = messages.iter().filter_map(|m| m.timestamp);
+        PySession {
+            start_ts: timestamps.clone().min(),
+            end_ts: timestamps.max(),
+            participants,
+            messages,
+        }
+    }
+}
+
+/// Check the gap argument
+pub fn check_gap(gap: TimeDelta) -> PyResult<TimeDelta> {
+    if gap <= TimeDelta::zero() {
+        return Err(PyValueError::new_err("gap must be positive"));
+    }
+    Ok(gap)
+}
+
+/// Incremental splitter: feed messages one by one, get a session back as soon
+/// as a pause ends it
+pub struct Sessionizer {
+    gap: TimeDelta,
+    current: Vec<PyMessage>,
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl Sessionizer {
+    pub fn new(gap: TimeDelta) -> Self {
+        Sessionizer {
+            gap,
+            current: Vec::new(),
+            last_timestamp: None,
+        }
+    }
+
+    /// Add a message, returning the previous session if the pause before it is too long
+    pub fn push(&mut self, msg: PyMessage) -> Option<PySession> {
+        let idle = match (self.last_timestamp, msg.timestamp) {
+            (Some(last), Some(ts)) => ts - last > self.gap,
+            _ => false,
+        };
+        if msg.timestamp.is_some() {
+            self.last_timestamp = msg.timestamp;
+        }
+
+        let finished = idle.then(|| PySession::new(std::mem::take(&mut self.current)));
+        self.current.push(msg);
+        finished
+    }
+
+    /// The last session, None if no messages were pushed since the previous one
+    pub fn finish(&mut self) -> Option<PySession> {
+        self.last_timestamp = None;
+        (!self.current.is_empty()).then(|| PySession::new(std::mem::take(&mut self.current)))
+    }
+}
+
+/// Group messages into sessions
+pub fn split(messages: Vec<PyMessage>, gap: TimeDelta) -> Vec<PySession> {
+    let mut sessionizer = Sessionizer::new(gap);
+    let mut sessions: Vec<PySession> = messages
+        .into_iter()
+        .filter_map(|msg| sessionizer.push(msg))
+        .collect();
+    sessions.extend(sessionizer.finish());
+    sessions
+}
@@ -0,0 +1,107 @@
+// Input sources accepted by the parse entry points: a path (str or os.PathLike),
+// bytes-like objects (bytes, bytearray, memoryview) or a file-like object with .read()
+
+use crate::errors::{ErrorInfo, ErrorKind};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+pub enum InputSource<'py> {
+    /// Filesystem path, parsed by the upstream file parser
+    Path(PathBuf),
+    /// `bytes` object, borrowed without copying
+    Bytes(Bound<'py, PyBytes>),
+    /// Any other buffer-protocol object (bytearray, memoryview, ...), copied before parsing
+    Buffer(PyBuffer<u8>),
+    /// Content returned by a file-like object's `.read()`
+    Owned(Vec<u8>),
+}
+
+impl<'py> FromPyObject<'py> for InputSource<'py> {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if ob.is_instance_of::<PyString>() {
+            return Ok(InputSource::Path(ob.extract()?));
+        }
+
+        if let Ok(bytes) = ob.downcast::<PyBytes>() {
+            return Ok(InputSource::Bytes(bytes.clone()));
+        }
+
+        if let Ok(buffer) = PyBuffer::<u8>::get_bound(ob) {
+            return Ok(InputSource::Buffer(buffer));
+        }
+
+        if ob.hasattr("read")? {
+            let data = ob.call_method0("read")?;
+            if let Ok(text) = data.downcast::<PyString>() {
+                return Ok(InputSource::Owned(text.to_str()?.as_bytes().to_vec()));
+            }
+            return match InputSource::extract_bound(&data)? {
+                InputSource::Path(_) => unreachable!("str handled above"),
+                source => Ok(source),
+            };
+        }
+
+        // os.PathLike (pathlib.Path etc.)
+        if let Ok(path) = ob.extract::<PathBuf>() {
+            return Ok(InputSource::Path(path));
+        }
+
+        Err(PyTypeError::new_err(format!(
+            "Expected a path, bytes-like object or file-like object, got {}",
+            ob.get_type().name()?
+        )))
+    }
+}
+
+impl InputSource<'_> {
+    /// Filesystem path, if this source is one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            InputSource::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Run `f` on the raw content.
+    /// Borrows `bytes` objects directly, every other buffer is copied
+    pub fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> PyResult<R>) -> PyResult<R> {
+        match self {
+            InputSource::Path(path) => {
+                let data = std::fs::read(path).map_err(|e| ErrorInfo::io(&e, path))?;
+                f(&data)
+            }
+            // bytes are immutable, so the slice stays valid with the GIL released
+            InputSource::Bytes(bytes) => f(bytes.as_bytes()),
+            // Other buffers are copied: parsing runs with the GIL released, and even a
+            // read-only memoryview may wrap a bytearray that another thread modifies
+            InputSource::Buffer(buffer) => {
+                let data = Python::with_gil(|py| buffer.to_vec(py))?;
+                f(&data)
+            }
+            InputSource::Owned(data) => f(data),
+        }
+    }
+}
+
+/// First `limit` bytes of a file
+pub fn read_head(path: &Path, limit: usize) -> Result<Vec<u8>, ErrorInfo> {
+    let mut data = Vec::with_capacity(limit);
+    std::fs::File::open(path)
+        .and_then(|file| file.take(limit as u64).read_to_end(&mut data))
+        .map_err(|e| ErrorInfo::io(&e, path))?;
+    Ok(data)
+}
+
+// Helper: decode raw bytes as UTF-8 before parsing
+pub fn decode_utf8(data: &[u8]) -> Result<&str, ErrorInfo> {
+    std::str::from_utf8(data).map_err(utf8_error)
+}
+
+pub fn utf8_error(err: std::str::Utf8Error) -> ErrorInfo {
+    ErrorInfo::new(ErrorKind::Parse, format!("UTF-8 error: {}", err))
+        .with_byte_offset(err.valid_up_to() as u64)
+}
@@ -1,16 +1,139 @@
-use crate::types::PyMessage;
+use crate::arrow;
+use crate::asyncio;
+use crate::errors::ErrorInfo;
+use crate::parsers::Pipeline;
+use crate::processing::Merger;
+use crate::redact::{PyRedactor, RedactArg};
+use crate::sessions::{self, PySession, Sessionizer};
+use crate::types::{MergeArg, PyFilterConfig, PyMessage, PyPlatform};
 use chatpack::streaming::{
     DiscordStreamingParser, InstagramStreamingParser, StreamingParser, TelegramStreamingParser,
     WhatsAppStreamingParser,
 };
-use pyo3::exceptions::{PyStopIteration, PyValueError};
+use chrono::TimeDelta;
+use pyo3::exceptions::{PyStopAsyncIteration, PyStopIteration, PyValueError};
 use pyo3::prelude::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
 
 // Универсальный итератор для Python
 #[pyclass]
-struct StreamIterator {
+pub struct StreamIterator {
     // Храним итератор как Box<dyn ...>
     iter: Box<dyn chatpack::streaming::MessageIterator>,
+    platform: PyPlatform,
+    path: String,
+    // Фильтры применяются здесь, до пересечения границы с Python
+    pipeline: Pipeline,
+    // Буфер только для текущей серии сообщений одного отправителя
+    merger: Option<Merger>,
+    redactor: Option<PyRedactor>,
+}
+
+// Helper: build the filter/merge pipeline, keyword arguments override the FilterConfig
+fn stream_pipeline(
+    filter: Option<PyFilterConfig>,
+    merge: Option<MergeArg>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    sender: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+) -> PyResult<Pipeline> {
+    let mut config = filter.unwrap_or_default();
+
+    if min_length.is_some() {
+        config.min_length = min_length;
+    }
+    if max_length.is_some() {
+        config.max_length = max_length;
+    }
+    if sender.is_some() {
+        config.sender = sender;
+    }
+    if date_from.is_some() {
+        config.date_from = date_from;
+    }
+    if date_to.is_some() {
+        config.date_to = date_to;
+    }
+
+    Pipeline::from_config(Some(config), MergeArg::resolve(merge))
+}
+
+// Helper: open the upstream stream, attaching path and platform to errors
+fn start_stream(
+    parser: impl StreamingParser,
+    path: &str,
+    platform: PyPlatform,
+    pipeline: &Pipeline,
+    redactor: Option<PyRedactor>,
+) -> PyResult<StreamIterator> {
+    let stream = parser
+        .stream(path)
+        .map_err(|e| ErrorInfo::from(e).with_path(path).with_platform(platform))?;
+
+    Ok(StreamIterator {
+        iter: stream,
+        platform,
+        path: path.to_string(),
+        pipeline: pipeline.clone(),
+        merger: pipeline.merger(),
+        redactor,
+    })
+}
+
+impl StreamIterator {
+    /// Up to `size` messages, fewer only at the end of the stream
+    pub fn next_batch(&mut self, size: usize) -> Result<Vec<PyMessage>, ErrorInfo> {
+        let mut batch = Vec::with_capacity(size.min(1024));
+        while batch.len() < size {
+            match self.next_message()? {
+                Some(msg) => batch.push(PyMessage::from_rust(msg, Some(self.platform))),
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Next message after filtering, merging and redaction, None at the end of the stream
+    fn next_message(&mut self) -> Result<Option<chatpack::Message>, ErrorInfo> {
+        let mut next = self.next_processed()?;
+        if let (Some(redactor), Some(msg)) = (&self.redactor, &mut next) {
+            redactor.inner.redact_upstream(msg);
+        }
+        Ok(next)
+    }
+
+    // Filters see the original senders, redaction comes last
+    fn next_processed(&mut self) -> Result<Option<chatpack::Message>, ErrorInfo> {
+        loop {
+            let msg = match self.iter.next() {
+                Some(Ok(msg)) => msg,
+                Some(Err(e)) => {
+                    return Err(ErrorInfo::from(e)
+                        .with_path(&self.path)
+                        .with_platform(self.platform)
+                        .with_byte_offset(self.iter.bytes_processed()))
+                }
+                // Flush the last buffered run
+                None => return Ok(self.merger.as_mut().and_then(Merger::finish)),
+            };
+
+            let Some(msg) = self.pipeline.filter_one(msg) else {
+                continue;
+            };
+
+            match &mut self.merger {
+                Some(merger) => {
+                    if let Some(merged) = merger.push(msg) {
+                        return Ok(Some(merged));
+                    }
+                }
+                None => return Ok(Some(msg)),
+            }
+        }
+    }
 }
 
 #[pymethods]
@@ -20,109 +143,281 @@ impl StreamIterator {
     }
 
     fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<PyMessage>> {
-        match slf.iter.next() {
-            Some(Ok(msg)) => Ok(Some(PyMessage::from_rust(msg))),
-            Some(Err(e)) => Err(PyValueError::new_err(format!("Streaming error: {}", e))),
+        let py = slf.py();
+        let this = &mut *slf;
+
+        match py.allow_threads(|| this.next_message())? {
+            Some(msg) => Ok(Some(PyMessage::from_rust(msg, Some(this.platform)))),
             None => Err(PyStopIteration::new_err("End of stream")),
         }
     }
 }
 
-// --- Telegram ---
-
+// Итератор пачками: одно пересечение границы Python/Rust на batch_size сообщений
 #[pyclass]
-pub struct TelegramStreamParser {
-    path: String,
+struct BatchIterator {
+    inner: StreamIterator,
+    batch_size: usize,
+    arrow: bool,
 }
 
-#[pymethods]
-impl TelegramStreamParser {
-    #[new]
-    fn new(path: String) -> Self {
-        TelegramStreamParser { path }
-    }
+impl BatchIterator {
+    // `arrow` = None picks Arrow record batches when pyarrow is installed
+    fn new(
+        py: Python<'_>,
+        inner: StreamIterator,
+        batch_size: usize,
+        arrow: Option<bool>,
+    ) -> PyResult<Self> {
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("batch_size must be positive"));
+        }
 
-    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<StreamIterator> {
-        let parser = TelegramStreamingParser::new();
-        // Исправление: передаем строку напрямую, без Path::new
-        let stream = parser
-            .stream(&slf.path)
-            .map_err(|e| PyValueError::new_err(format!("Failed to start stream: {}", e)))?;
+        let arrow = match arrow {
+            Some(true) => arrow::import(py).map(|_| true)?,
+            Some(false) => false,
+            None => arrow::is_available(py),
+        };
 
-        Ok(StreamIterator {
-            iter: stream, // Исправление: stream уже является Box, не нужно Box::new
+        Ok(BatchIterator {
+            inner,
+            batch_size,
+            arrow,
         })
     }
 }
 
-// --- WhatsApp ---
+#[pymethods]
+impl BatchIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
 
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyObject> {
+        let py = slf.py();
+        let this = &mut *slf;
+        let batch = py.allow_threads(|| this.inner.next_batch(this.batch_size))?;
+
+        if batch.is_empty() {
+            return Err(PyStopIteration::new_err("End of stream"));
+        }
+
+        if slf.arrow {
+            Ok(arrow::record_batch(py, &batch)?.unbind())
+        } else {
+            Ok(batch.into_py(py))
+        }
+    }
+}
+
+// Итератор по сессиям: в памяти только текущая сессия
 #[pyclass]
-pub struct WhatsAppStreamParser {
-    path: String,
+struct SessionIterator {
+    inner: StreamIterator,
+    sessionizer: Sessionizer,
 }
 
 #[pymethods]
-impl WhatsAppStreamParser {
-    #[new]
-    fn new(path: String) -> Self {
-        WhatsAppStreamParser { path }
+impl SessionIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
     }
 
-    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<StreamIterator> {
-        let parser = WhatsAppStreamingParser::new();
-        let stream = parser
-            .stream(&slf.path)
-            .map_err(|e| PyValueError::new_err(format!("Failed to start stream: {}", e)))?;
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<PySession> {
+        let py = slf.py();
+        let this = &mut *slf;
 
-        Ok(StreamIterator { iter: stream })
+        let session = py.allow_threads(|| loop {
+            match this.inner.next_message()? {
+                Some(msg) => {
+                    let msg = PyMessage::from_rust(msg, Some(this.inner.platform));
+                    if let Some(session) = this.sessionizer.push(msg) {
+                        return Ok::<_, ErrorInfo>(Some(session));
+                    }
+                }
+                None => return Ok(this.sessionizer.finish()),
+            }
+        })?;
+
+        session.ok_or_else(|| PyStopIteration::new_err("End of stream"))
     }
 }
 
-// --- Instagram ---
-
+// Асинхронный итератор: сообщения читаются пачками в рабочем потоке,
+// __anext__ из буфера завершается сразу, без похода в поток
 #[pyclass]
-pub struct InstagramStreamParser {
-    path: String,
+pub struct AsyncStreamIterator {
+    state: Arc<Mutex<AsyncStreamState>>,
+}
+
+struct AsyncStreamState {
+    stream: StreamIterator,
+    buffer: VecDeque<PyMessage>,
+}
+
+/// Messages read per trip to the worker thread
+const ASYNC_BATCH_SIZE: usize = 1024;
+
+impl AsyncStreamIterator {
+    fn new(stream: StreamIterator) -> Self {
+        AsyncStreamIterator {
+            state: Arc::new(Mutex::new(AsyncStreamState {
+                stream,
+                buffer: VecDeque::new(),
+            })),
+        }
+    }
 }
 
 #[pymethods]
-impl InstagramStreamParser {
-    #[new]
-    fn new(path: String) -> Self {
-        InstagramStreamParser { path }
+impl AsyncStreamIterator {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
     }
 
-    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<StreamIterator> {
-        let parser = InstagramStreamingParser::new();
-        let stream = parser
-            .stream(&slf.path)
-            .map_err(|e| PyValueError::new_err(format!("Failed to start stream: {}", e)))?;
+    fn __anext__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        // try_lock: the event loop must not wait for a batch being read
+        if let Ok(mut state) = self.state.try_lock() {
+            if let Some(msg) = state.buffer.pop_front() {
+                return asyncio::ready(py, msg.into_py(py));
+            }
+        }
+
+        let state = Arc::clone(&self.state);
+        asyncio::spawn(py, move |py| {
+            let next = py.allow_threads(|| {
+                let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                if state.buffer.is_empty() {
+                    let batch = state.stream.next_batch(ASYNC_BATCH_SIZE)?;
+                    state.buffer.extend(batch);
+                }
+                Ok::<_, ErrorInfo>(state.buffer.pop_front())
+            })?;
 
-        Ok(StreamIterator { iter: stream })
+            match next {
+                Some(msg) => Ok(msg.into_py(py)),
+                None => Err(PyStopAsyncIteration::new_err("End of stream")),
+            }
+        })
     }
 }
 
-// --- Discord ---
+// The stream parser classes differ only in the upstream parser and platform
+macro_rules! stream_parser_class {
+    ($name:ident, $upstream:ty, $platform:expr) => {
+        #[pyclass]
+        pub struct $name {
+            path: String,
+            pipeline: Pipeline,
+            redactor: Option<PyRedactor>,
+        }
 
-#[pyclass]
-pub struct DiscordStreamParser {
-    path: String,
+        impl $name {
+            fn start(&self) -> PyResult<StreamIterator> {
+                start_stream(
+                    <$upstream>::new(),
+                    &self.path,
+                    $platform,
+                    &self.pipeline,
+                    self.redactor.clone(),
+                )
+            }
+        }
+
+        #[pymethods]
+        impl $name {
+            #[new]
+            #[pyo3(signature = (path, filter=None, merge=None, min_length=None, max_length=None, sender=None, date_from=None, date_to=None, redact=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn new(
+                path: String,
+                filter: Option<PyFilterConfig>,
+                merge: Option<MergeArg>,
+                min_length: Option<usize>,
+                max_length: Option<usize>,
+                sender: Option<String>,
+                date_from: Option<String>,
+                date_to: Option<String>,
+                redact: Option<RedactArg>,
+            ) -> PyResult<Self> {
+                Ok($name {
+                    path,
+                    pipeline: stream_pipeline(
+                        filter, merge, min_length, max_length, sender, date_from, date_to,
+                    )?,
+                    redactor: RedactArg::resolve(redact)?,
+                })
+            }
+
+            fn __iter__(slf: PyRef<'_, Self>) -> PyResult<StreamIterator> {
+                slf.start()
+            }
+
+            fn __aiter__(slf: PyRef<'_, Self>) -> PyResult<AsyncStreamIterator> {
+                Ok(AsyncStreamIterator::new(slf.start()?))
+            }
+
+            /// Iterate in lists of up to `batch_size` messages, or Arrow record batches
+            #[pyo3(signature = (batch_size=10_000, arrow=None))]
+            fn iter_batches(
+                slf: PyRef<'_, Self>,
+                batch_size: usize,
+                arrow: Option<bool>,
+            ) -> PyResult<BatchIterator> {
+                let stream = slf.start()?;
+                BatchIterator::new(slf.py(), stream, batch_size, arrow)
+            }
+
+            /// Iterate over conversation sessions split by pauses longer than `gap`
+            #[pyo3(signature = (gap=TimeDelta::hours(2)))]
+            fn iter_sessions(slf: PyRef<'_, Self>, gap: TimeDelta) -> PyResult<SessionIterator> {
+                Ok(SessionIterator {
+                    sessionizer: Sessionizer::new(sessions::check_gap(gap)?),
+                    inner: slf.start()?,
+                })
+            }
+        }
+    };
 }
 
-#[pymethods]
-impl DiscordStreamParser {
-    #[new]
-    fn new(path: String) -> Self {
-        DiscordStreamParser { path }
-    }
+stream_parser_class!(
+    TelegramStreamParser,
+    TelegramStreamingParser,
+    PyPlatform::Telegram
+);
+stream_parser_class!(
+    WhatsAppStreamParser,
+    WhatsAppStreamingParser,
+    PyPlatform::WhatsApp
+);
+stream_parser_class!(
+    InstagramStreamParser,
+    InstagramStreamingParser,
+    PyPlatform::Instagram
+);
+stream_parser_class!(
+    DiscordStreamParser,
+    DiscordStreamingParser,
+    PyPlatform::Discord
+);
 
-    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<StreamIterator> {
-        let parser = DiscordStreamingParser::new();
-        let stream = parser
-            .stream(&slf.path)
-            .map_err(|e| PyValueError::new_err(format!("Failed to start stream: {}", e)))?;
+/// Any of the stream parser classes, for functions that consume a stream
+#[derive(FromPyObject)]
+pub enum AnyStreamParser<'py> {
+    Telegram(PyRef<'py, TelegramStreamParser>),
+    WhatsApp(PyRef<'py, WhatsAppStreamParser>),
+    Instagram(PyRef<'py, InstagramStreamParser>),
+    Discord(PyRef<'py, DiscordStreamParser>),
+}
 
-        Ok(StreamIterator { iter: stream })
+impl AnyStreamParser<'_> {
+    /// Open a new pass over the export
+    pub fn start(&self) -> PyResult<StreamIterator> {
+        match self {
+            AnyStreamParser::Telegram(parser) => parser.start(),
+            AnyStreamParser::WhatsApp(parser) => parser.start(),
+            AnyStreamParser::Instagram(parser) => parser.start(),
+            AnyStreamParser::Discord(parser) => parser.start(),
+        }
     }
 }
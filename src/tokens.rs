@@ -0,0 +1,134 @@
+// Token counting for LLM context windows
+//
+// cl100k_base (GPT-4, GPT-3.5) and o200k_base (GPT-4o) BPE tables are bundled
+// with tiktoken-rs and loaded on first use; "chars" is the usual ~4 characters
+// per token estimate. Any Python tokenizer can be plugged in as well: an object
+// with .encode(text) (tiktoken, Hugging Face) or a callable returning a count
+
+use crate::conversion::render_messages;
+use crate::types::{PyMessage, PyOutputConfig};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use std::ops::Range;
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+pub enum Tokenizer {
+    Cl100k,
+    O200k,
+    Chars,
+    /// Object with .encode(text) returning a sequence of tokens
+    Encoder(PyObject),
+    /// Callable returning the token count of a text
+    Callable(PyObject),
+}
+
+impl<'py> FromPyObject<'py> for Tokenizer {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(name) = ob.downcast::<PyString>() {
+            return match name.to_str()?.to_ascii_lowercase().as_str() {
+                "cl100k" | "cl100k_base" => Ok(Tokenizer::Cl100k),
+                "o200k" | "o200k_base" => Ok(Tokenizer::O200k),
+                "chars" => Ok(Tokenizer::Chars),
+                other => Err(PyValueError::new_err(format!(
+                    "Unknown tokenizer: '{}'. Expected one of: cl100k, o200k, chars",
+                    other
+                ))),
+            };
+        }
+
+        if ob.hasattr("encode")? {
+            return Ok(Tokenizer::Encoder(ob.clone().unbind()));
+        }
+        if ob.is_callable() {
+            return Ok(Tokenizer::Callable(ob.clone().unbind()));
+        }
+
+        Err(PyTypeError::new_err(format!(
+            "Expected a tokenizer name, an object with .encode() or a callable, got {}",
+            ob.get_type().name()?
+        )))
+    }
+}
+
+fn cl100k() -> &'static CoreBPE {
+    static BPE: OnceLock<CoreBPE> = OnceLock::new();
+    BPE.get_or_init(|| tiktoken_rs::cl100k_base().expect("bundled cl100k_base table"))
+}
+
+fn o200k() -> &'static CoreBPE {
+    static BPE: OnceLock<CoreBPE> = OnceLock::new();
+    BPE.get_or_init(|| tiktoken_rs::o200k_base().expect("bundled o200k_base table"))
+}
+
+impl Tokenizer {
+    /// Number of tokens in `text`, the built-in tokenizers run without the GIL
+    pub fn count(&self, py: Python<'_>, text: &str) -> PyResult<usize> {
+        match self {
+            Tokenizer::Cl100k => Ok(py.allow_threads(|| cl100k().encode_ordinary(text).len())),
+            Tokenizer::O200k => Ok(py.allow_threads(|| o200k().encode_ordinary(text).len())),
+            Tokenizer::Chars => Ok(text.chars().count().div_ceil(4)),
+            Tokenizer::Encoder(encoder) => encoder.bind(py).call_method1("encode", (text,))?.len(),
+            Tokenizer::Callable(count) => count.bind(py).call1((text,))?.extract(),
+        }
+    }
+}
+
+/// Tokens of the rendered messages
+pub fn count_messages(
+    py: Python<'_>,
+    messages: &[PyMessage],
+    format: &str,
+    config: &PyOutputConfig,
+    tokenizer: &Tokenizer,
+) -> PyResult<usize> {
+    let rendered = py.allow_threads(|| render_messages(messages, format, config))?;
+    tokenizer.count(py, &rendered)
+}
+
+/// Split messages into windows whose rendering fits in `max_tokens`, on message
+/// boundaries. Each window repeats the last `overlap` messages of the previous one;
+/// a single message over the budget gets a window of its own
+pub fn chunk_messages(
+    py: Python<'_>,
+    messages: &[PyMessage],
+    max_tokens: usize,
+    overlap: usize,
+    format: &str,
+    config: &PyOutputConfig,
+    tokenizer: &Tokenizer,
+) -> PyResult<Vec<Range<usize>>> {
+    let count = |messages: &[PyMessage]| count_messages(py, messages, format, config, tokenizer);
+
+    // Header, brackets etc. are paid once per window
+    let overhead = count(&[])?;
+    let costs = messages
+        .iter()
+        .map(|msg| Ok(count(std::slice::from_ref(msg))?.saturating_sub(overhead)))
+        .collect::<PyResult<Vec<usize>>>()?;
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < messages.len() {
+        let mut end = start;
+        let mut total = overhead;
+        while end < messages.len() && (end == start || total + costs[end] <= max_tokens) {
+            total += costs[end];
+            end += 1;
+        }
+
+        // Per-message costs are an estimate, the rendered window is authoritative
+        while end - start > 1 && count(&messages[start..end])? > max_tokens {
+            end -= 1;
+        }
+
+        windows.push(start..end);
+        if end == messages.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+
+    Ok(windows)
+}
@@ -1,8 +1,144 @@
 #![allow(clippy::useless_conversion)]
-use chrono::{DateTime, Utc};
-use pyo3::exceptions::PyValueError;
+use crate::errors::{ErrorInfo, ErrorKind};
+use crate::processing::{LengthFilter, MergeOptions};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
+use pyo3::types::{timezone_utc_bound, PyDateTime, PyDict, PyString, PyTzInfoAccess};
+use std::str::FromStr;
+
+/// Source platform of a message
+#[pyclass(eq, eq_int, hash, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PyPlatform {
+    #[pyo3(name = "TELEGRAM")]
+    Telegram,
+    #[pyo3(name = "WHATSAPP")]
+    WhatsApp,
+    #[pyo3(name = "INSTAGRAM")]
+    Instagram,
+    #[pyo3(name = "DISCORD")]
+    Discord,
+}
+
+#[pymethods]
+impl PyPlatform {
+    /// Parse a platform name or alias ("telegram", "tg", "wa", ...)
+    #[staticmethod]
+    #[pyo3(name = "from_str")]
+    fn py_from_str(name: &str) -> PyResult<Self> {
+        Ok(name.parse::<Self>()?)
+    }
+
+    /// Lowercase platform name, e.g. "telegram"
+    #[getter]
+    fn value(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn __str__(&self) -> &'static str {
+        self.as_str()
+    }
+}
+
+impl PyPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PyPlatform::Telegram => "telegram",
+            PyPlatform::WhatsApp => "whatsapp",
+            PyPlatform::Instagram => "instagram",
+            PyPlatform::Discord => "discord",
+        }
+    }
+}
+
+impl FromStr for PyPlatform {
+    type Err = ErrorInfo;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let platform = s
+            .parse::<chatpack::prelude::Platform>()
+            .map_err(|e| ErrorInfo::new(ErrorKind::UnsupportedFormat, e))?;
+        Self::try_from(platform)
+    }
+}
+
+impl TryFrom<chatpack::prelude::Platform> for PyPlatform {
+    type Error = ErrorInfo;
+
+    fn try_from(platform: chatpack::prelude::Platform) -> Result<Self, Self::Error> {
+        use chatpack::prelude::Platform;
+        match platform {
+            Platform::Telegram => Ok(PyPlatform::Telegram),
+            Platform::WhatsApp => Ok(PyPlatform::WhatsApp),
+            Platform::Instagram => Ok(PyPlatform::Instagram),
+            Platform::Discord => Ok(PyPlatform::Discord),
+            // Platform is #[non_exhaustive] upstream, newer releases may add variants
+            other => Err(ErrorInfo::new(
+                ErrorKind::UnsupportedFormat,
+                format!("Unsupported platform: {}", other),
+            )),
+        }
+    }
+}
+
+impl From<PyPlatform> for chatpack::prelude::Platform {
+    fn from(platform: PyPlatform) -> Self {
+        use chatpack::prelude::Platform;
+        match platform {
+            PyPlatform::Telegram => Platform::Telegram,
+            PyPlatform::WhatsApp => Platform::WhatsApp,
+            PyPlatform::Instagram => Platform::Instagram,
+            PyPlatform::Discord => Platform::Discord,
+        }
+    }
+}
+
+/// Platform argument accepted from Python: either a `Platform` or its name
+#[derive(FromPyObject)]
+pub enum PlatformArg {
+    Enum(PyPlatform),
+    Name(String),
+}
+
+impl PlatformArg {
+    pub fn resolve(self) -> PyResult<PyPlatform> {
+        match self {
+            PlatformArg::Enum(platform) => Ok(platform),
+            PlatformArg::Name(name) => Ok(name.parse::<PyPlatform>()?),
+        }
+    }
+}
+
+/// Timestamp accepted from Python: an aware datetime in any timezone,
+/// a naive datetime (taken as UTC) or an RFC 3339 string
+pub struct TimestampArg(pub DateTime<Utc>);
+
+impl<'py> FromPyObject<'py> for TimestampArg {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(text) = ob.downcast::<PyString>() {
+            return Ok(TimestampArg(parse_timestamp(text.to_str()?)?));
+        }
+
+        let dt = ob.downcast::<PyDateTime>()?;
+        if dt.get_tzinfo_bound().is_none() {
+            let naive: NaiveDateTime = dt.extract()?;
+            return Ok(TimestampArg(naive.and_utc()));
+        }
+
+        let utc = dt.call_method1("astimezone", (timezone_utc_bound(ob.py()),))?;
+        Ok(TimestampArg(utc.extract()?))
+    }
+}
+
+/// Parse an RFC 3339 timestamp, e.g. "2024-01-15T10:30:00Z"
+pub fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, ErrorInfo> {
+    s.parse::<DateTime<Utc>>().map_err(|e| {
+        ErrorInfo::new(
+            ErrorKind::InvalidDate,
+            format!("Invalid timestamp '{}': {} (expected RFC 3339)", s, e),
+        )
+    })
+}
 
 /// Python wrapper for chatpack::Message
 #[pyclass]
@@ -14,29 +150,91 @@ pub struct PyMessage {
     #[pyo3(get, set)]
     pub content: String,
 
+    /// Timezone-aware (UTC) datetime
+    #[pyo3(get)]
+    pub timestamp: Option<DateTime<Utc>>,
+
+    pub platform: Option<PyPlatform>,
+
     #[pyo3(get, set)]
-    pub timestamp: Option<String>,
+    pub id: Option<u64>,
 
     #[pyo3(get, set)]
-    pub platform: Option<String>,
+    pub reply_to: Option<u64>,
+
+    #[pyo3(get)]
+    pub edited: Option<DateTime<Utc>>,
+
+    /// Conversation title (Instagram conversation directories)
+    #[pyo3(get, set)]
+    pub conversation: Option<String>,
 }
 
 #[pymethods]
 impl PyMessage {
     #[new]
-    #[pyo3(signature = (sender, content, timestamp=None, platform=None))]
+    #[pyo3(signature = (sender, content, timestamp=None, platform=None, id=None, reply_to=None, edited=None, conversation=None))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         sender: String,
         content: String,
-        timestamp: Option<String>,
-        platform: Option<String>,
-    ) -> Self {
-        PyMessage {
+        timestamp: Option<TimestampArg>,
+        platform: Option<PlatformArg>,
+        id: Option<u64>,
+        reply_to: Option<u64>,
+        edited: Option<TimestampArg>,
+        conversation: Option<String>,
+    ) -> PyResult<Self> {
+        Ok(PyMessage {
             sender,
             content,
-            timestamp,
-            platform,
-        }
+            timestamp: timestamp.map(|ts| ts.0),
+            platform: platform.map(PlatformArg::resolve).transpose()?,
+            id,
+            reply_to,
+            edited: edited.map(|ts| ts.0),
+            conversation,
+        })
+    }
+
+    #[setter]
+    fn set_timestamp(&mut self, timestamp: Option<TimestampArg>) {
+        self.timestamp = timestamp.map(|ts| ts.0);
+    }
+
+    #[setter]
+    fn set_edited(&mut self, edited: Option<TimestampArg>) {
+        self.edited = edited.map(|ts| ts.0);
+    }
+
+    /// Timestamp as an RFC 3339 string, e.g. "2024-01-15T10:30:00+00:00"
+    #[getter]
+    fn timestamp_str(&self) -> Option<String> {
+        self.timestamp_rfc3339()
+    }
+
+    /// Timestamp as milliseconds since the Unix epoch
+    #[getter]
+    fn epoch_ms(&self) -> Option<i64> {
+        self.timestamp.map(|ts| ts.timestamp_millis())
+    }
+
+    /// Platform name as a string, e.g. "telegram"
+    #[getter]
+    fn platform(&self) -> Option<&'static str> {
+        self.platform.map(|p| p.as_str())
+    }
+
+    #[setter]
+    fn set_platform(&mut self, platform: Option<PlatformArg>) -> PyResult<()> {
+        self.platform = platform.map(PlatformArg::resolve).transpose()?;
+        Ok(())
+    }
+
+    /// Platform as a `Platform` enum member
+    #[getter]
+    fn platform_enum(&self) -> Option<PyPlatform> {
+        self.platform
     }
 
     fn __repr__(&self) -> String {
@@ -44,7 +242,7 @@ impl PyMessage {
             "Message(sender='{}', content='{}...', timestamp={})",
             self.sender,
             &self.content.chars().take(30).collect::<String>(),
-            self.timestamp.as_deref().unwrap_or("None")
+            self.timestamp_rfc3339().as_deref().unwrap_or("None")
         )
     }
 
@@ -57,37 +255,49 @@ impl PyMessage {
         let dict = PyDict::new_bound(py);
         dict.set_item("sender", &self.sender)?;
         dict.set_item("content", &self.content)?;
-        dict.set_item("timestamp", &self.timestamp)?;
-        dict.set_item("platform", &self.platform)?;
+        dict.set_item("timestamp", self.timestamp)?;
+        dict.set_item("platform", self.platform.map(|p| p.as_str()))?;
+        dict.set_item("id", self.id)?;
+        dict.set_item("reply_to", self.reply_to)?;
+        dict.set_item("edited", self.edited)?;
+        dict.set_item("conversation", &self.conversation)?;
         Ok(dict.into())
     }
 }
 
 impl PyMessage {
-    pub fn from_rust(msg: chatpack::Message) -> Self {
+    pub fn from_rust(msg: chatpack::Message, platform: Option<PyPlatform>) -> Self {
         PyMessage {
             sender: msg.sender,
             content: msg.content,
-            timestamp: msg.timestamp.map(|ts| ts.to_rfc3339()),
-            platform: None,
+            timestamp: msg.timestamp,
+            platform,
+            id: msg.id,
+            reply_to: msg.reply_to,
+            edited: msg.edited,
+            conversation: None,
         }
     }
 
+    pub fn timestamp_rfc3339(&self) -> Option<String> {
+        self.timestamp.map(|ts| ts.to_rfc3339())
+    }
+
     pub fn into_rust(self) -> chatpack::Message {
         chatpack::Message {
             sender: self.sender,
             content: self.content,
-            timestamp: self.timestamp.and_then(|s| s.parse::<DateTime<Utc>>().ok()),
-            id: None,
-            reply_to: None,
-            edited: None,
+            timestamp: self.timestamp,
+            id: self.id,
+            reply_to: self.reply_to,
+            edited: self.edited,
         }
     }
 }
 
 /// Filter configuration for messages
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct PyFilterConfig {
     #[pyo3(get, set)]
     pub min_length: Option<usize>,
@@ -103,18 +313,22 @@ pub struct PyFilterConfig {
 
     #[pyo3(get, set)]
     pub date_to: Option<String>,
+
+    #[pyo3(get, set)]
+    pub truncate: bool,
 }
 
 #[pymethods]
 impl PyFilterConfig {
     #[new]
-    #[pyo3(signature = (min_length=None, max_length=None, sender=None, date_from=None, date_to=None))]
+    #[pyo3(signature = (min_length=None, max_length=None, sender=None, date_from=None, date_to=None, truncate=false))]
     fn new(
         min_length: Option<usize>,
         max_length: Option<usize>,
         sender: Option<String>,
         date_from: Option<String>,
         date_to: Option<String>,
+        truncate: bool,
     ) -> Self {
         PyFilterConfig {
             min_length,
@@ -122,6 +336,7 @@ impl PyFilterConfig {
             sender,
             date_from,
             date_to,
+            truncate,
         }
     }
 
@@ -135,6 +350,12 @@ impl PyFilterConfig {
         slf
     }
 
+    /// Truncate messages longer than max_length (with an ellipsis) instead of dropping them
+    fn with_truncate(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.truncate = true;
+        slf
+    }
+
     fn with_sender(mut slf: PyRefMut<'_, Self>, sender: String) -> PyRefMut<'_, Self> {
         slf.sender = Some(sender);
         slf
@@ -152,39 +373,110 @@ impl PyFilterConfig {
 }
 
 impl PyFilterConfig {
+    pub fn length_filter(&self) -> LengthFilter {
+        LengthFilter {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            truncate: self.truncate,
+        }
+    }
+
     pub fn into_rust(self) -> PyResult<chatpack::core::filter::FilterConfig> {
         let mut config = chatpack::core::filter::FilterConfig::new();
 
-        // Исправление: метод with_min_length отсутствует в chatpack 0.5,
-        // мы будем фильтровать длину вручную в месте вызова,
-        // или использовать доступные методы.
-        // Пока пропускаем настройку длины здесь.
-        /*
-        if let Some(len) = self.min_length {
-            config = config.with_min_length(len);
-        }
-        */
+        // Длина (min/max/truncate) в chatpack 0.5 отсутствует,
+        // её применяет length_filter() в месте вызова
 
         if let Some(sender) = self.sender {
             config = config.with_sender(&sender);
         }
 
         if let Some(date) = self.date_from {
-            config = config
-                .with_date_from(&date)
-                .map_err(|e| PyValueError::new_err(format!("Invalid start date: {}", e)))?;
+            config = config.with_date_from(&date).map_err(ErrorInfo::from)?;
         }
 
         if let Some(date) = self.date_to {
-            config = config
-                .with_date_to(&date)
-                .map_err(|e| PyValueError::new_err(format!("Invalid end date: {}", e)))?;
+            config = config.with_date_to(&date).map_err(ErrorInfo::from)?;
         }
 
         Ok(config)
     }
 }
 
+/// Merge configuration for consecutive messages
+#[pyclass]
+#[derive(Clone)]
+pub struct PyMergeConfig {
+    #[pyo3(get, set)]
+    pub time_threshold: Option<i64>,
+
+    #[pyo3(get, set)]
+    pub max_merged_length: Option<usize>,
+
+    #[pyo3(get, set)]
+    pub separator: String,
+}
+
+#[pymethods]
+impl PyMergeConfig {
+    #[new]
+    #[pyo3(signature = (time_threshold=Some(300), max_merged_length=None, separator="\n".to_string()))]
+    fn new(
+        time_threshold: Option<i64>,
+        max_merged_length: Option<usize>,
+        separator: String,
+    ) -> Self {
+        PyMergeConfig {
+            time_threshold,
+            max_merged_length,
+            separator,
+        }
+    }
+
+    fn with_time_threshold(mut slf: PyRefMut<'_, Self>, seconds: i64) -> PyRefMut<'_, Self> {
+        slf.time_threshold = Some(seconds);
+        slf
+    }
+
+    fn with_max_merged_length(mut slf: PyRefMut<'_, Self>, length: usize) -> PyRefMut<'_, Self> {
+        slf.max_merged_length = Some(length);
+        slf
+    }
+
+    fn with_separator(mut slf: PyRefMut<'_, Self>, separator: String) -> PyRefMut<'_, Self> {
+        slf.separator = separator;
+        slf
+    }
+}
+
+impl PyMergeConfig {
+    pub fn into_rust(self) -> MergeOptions {
+        MergeOptions {
+            time_threshold: self.time_threshold,
+            max_merged_length: self.max_merged_length,
+            separator: self.separator,
+        }
+    }
+}
+
+/// `merge` argument accepted by the parse functions: a flag or a `MergeConfig`
+#[derive(FromPyObject)]
+pub enum MergeArg {
+    Flag(bool),
+    Config(PyMergeConfig),
+}
+
+impl MergeArg {
+    /// Resolve to merge options, None when merging is disabled
+    pub fn resolve(arg: Option<Self>) -> Option<MergeOptions> {
+        match arg {
+            None | Some(MergeArg::Flag(false)) => None,
+            Some(MergeArg::Flag(true)) => Some(MergeOptions::default()),
+            Some(MergeArg::Config(config)) => Some(config.into_rust()),
+        }
+    }
+}
+
 /// Output configuration for exports
 #[pyclass]
 #[derive(Clone)]
@@ -217,3 +509,9 @@ impl PyOutputConfig {
         slf
     }
 }
+
+impl Default for PyOutputConfig {
+    fn default() -> Self {
+        PyOutputConfig::new(true, false)
+    }
+}
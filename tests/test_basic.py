@@ -2,6 +2,7 @@
 
 import pytest
 import chatpack
+from datetime import datetime, timedelta, timezone
 from pathlib import Path
 
 
@@ -21,10 +22,35 @@ def test_message_with_metadata():
     )
     assert msg.sender == "Bob"
     assert msg.content == "Test message"
-    assert msg.timestamp == "2024-01-15T10:30:00Z"
+    assert msg.timestamp == datetime(2024, 1, 15, 10, 30, tzinfo=timezone.utc)
+    assert msg.timestamp_str == "2024-01-15T10:30:00+00:00"
     assert msg.platform == "telegram"
 
 
+def test_message_timestamps():
+    """Test timestamps are timezone-aware datetimes and invalid input raises"""
+    local = timezone(timedelta(hours=5))
+    msg = chatpack.Message(
+        "Alice", "Hi", timestamp=datetime(2024, 1, 15, 15, 30, tzinfo=local)
+    )
+    assert msg.timestamp == datetime(2024, 1, 15, 10, 30, tzinfo=timezone.utc)
+    assert msg.timestamp.tzinfo is not None
+    assert msg.epoch_ms == 1705314600000
+
+    # Naive datetimes are taken as UTC
+    msg.timestamp = datetime(2024, 1, 15, 10, 30)
+    assert msg.epoch_ms == 1705314600000
+
+    msg.timestamp = None
+    assert msg.timestamp_str is None
+    assert msg.epoch_ms is None
+
+    with pytest.raises(chatpack.InvalidDateError):
+        chatpack.Message("Alice", "Hi", timestamp="yesterday")
+    with pytest.raises(ValueError):
+        msg.edited = "15/01/2024"
+
+
 def test_message_to_dict():
     """Test converting Message to dictionary"""
     msg = chatpack.Message("Alice", "Hello")
@@ -34,6 +60,69 @@ def test_message_to_dict():
     assert d["content"] == "Hello"
 
 
+def test_message_thread_fields():
+    """Test id, reply_to and edited survive merge and filter round trips"""
+    messages = [
+        chatpack.Message("Alice", "Question?", id=1),
+        chatpack.Message(
+            "Bob", "Answer", id=2, reply_to=1, edited="2024-01-15T10:35:00+00:00"
+        ),
+    ]
+
+    assert messages[1].to_dict()["reply_to"] == 1
+
+    merged = chatpack.merge_consecutive(messages)
+    assert [m.id for m in merged] == [1, 2]
+    assert merged[1].reply_to == 1
+    assert merged[1].edited == datetime(2024, 1, 15, 10, 35, tzinfo=timezone.utc)
+
+    filtered = chatpack.apply_filters(messages, chatpack.FilterConfig(sender="Bob"))
+    assert filtered[0].id == 2
+    assert filtered[0].reply_to == 1
+
+
+def test_platform_enum():
+    """Test Platform enum and its string/enum views on Message"""
+    assert chatpack.Platform.from_str("tg") == chatpack.Platform.TELEGRAM
+    assert chatpack.Platform.WHATSAPP.value == "whatsapp"
+    assert str(chatpack.Platform.DISCORD) == "discord"
+
+    msg = chatpack.Message("Alice", "Hello", platform=chatpack.Platform.INSTAGRAM)
+    assert msg.platform == "instagram"
+    assert msg.platform_enum == chatpack.Platform.INSTAGRAM
+
+    msg.platform = "wa"
+    assert msg.platform_enum == chatpack.Platform.WHATSAPP
+
+    with pytest.raises(ValueError):
+        msg.platform = "myspace"
+
+
+def test_platform_from_parser():
+    """Test that parsers tag messages with their platform"""
+    parser = chatpack.WhatsAppParser()
+    messages = parser.parse_str("[1/15/24, 10:30:45 AM] Alice: Hello")
+
+    assert messages[0].platform == "whatsapp"
+    assert messages[0].platform_enum == chatpack.Platform.WHATSAPP
+
+
+def test_platform_survives_merge_and_filter():
+    """Test platform round trips and prevents cross-platform merging"""
+    messages = [
+        chatpack.Message("Alice", "Hi", platform="telegram"),
+        chatpack.Message("Alice", "Again", platform="telegram"),
+        chatpack.Message("Alice", "Elsewhere", platform="discord"),
+    ]
+
+    merged = chatpack.merge_consecutive(messages)
+    assert [m.platform for m in merged] == ["telegram", "discord"]
+    assert merged[0].content == "Hi\nAgain"
+
+    filtered = chatpack.apply_filters(messages, chatpack.FilterConfig(min_length=3))
+    assert [m.platform for m in filtered] == ["telegram", "discord"]
+
+
 def test_filter_config():
     """Test FilterConfig creation"""
     config = chatpack.FilterConfig(min_length=5, max_length=100, sender="Alice")
@@ -85,6 +174,116 @@ def test_streaming_parser_instantiation():
     assert discord_stream is not None
 
 
+def test_streaming_filters(tmp_path):
+    """Test filters are applied inside the streaming iterator"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n"
+        "[1/15/24, 10:31:00 AM] Bob: Hi there\n"
+        "[1/16/24, 9:00:00 AM] Alice: Good morning everyone\n"
+    )
+
+    stream = chatpack.WhatsAppStreamParser(str(path), sender="alice")
+    assert [m.content for m in stream] == ["Hello", "Good morning everyone"]
+
+    stream = chatpack.WhatsAppStreamParser(
+        str(path), min_length=6, date_to="2024-01-15"
+    )
+    assert [m.content for m in stream] == ["Hi there"]
+
+    config = chatpack.FilterConfig(date_from="2024-01-16")
+    stream = chatpack.WhatsAppStreamParser(str(path), filter=config, max_length=10)
+    assert list(stream) == []
+
+    with pytest.raises(chatpack.InvalidDateError):
+        chatpack.WhatsAppStreamParser(str(path), date_from="soon")
+
+
+def test_streaming_merge(tmp_path):
+    """Test merging consecutive messages while streaming"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:00 AM] Alice: Hello\n"
+        "[1/15/24, 10:31:00 AM] Alice: Anyone here?\n"
+        "[1/15/24, 10:32:00 AM] Bob: Hi\n"
+        "[1/15/24, 11:30:00 AM] Bob: Sorry, was away\n"
+    )
+
+    stream = chatpack.WhatsAppStreamParser(str(path), merge=True)
+    assert [m.content for m in stream] == [
+        "Hello\nAnyone here?",
+        "Hi",
+        "Sorry, was away",
+    ]
+
+    config = chatpack.MergeConfig(time_threshold=None, separator=" | ")
+    stream = chatpack.WhatsAppStreamParser(str(path), merge=config, min_length=3)
+    assert [m.content for m in stream] == ["Hello | Anyone here?", "Sorry, was away"]
+
+
+def test_streaming_batches(tmp_path):
+    """Test iterating a stream in batches"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "".join(f"[1/15/24, 10:{i:02d}:00 AM] User{i}: Message {i}\n" for i in range(5))
+    )
+
+    stream = chatpack.WhatsAppStreamParser(str(path))
+    batches = list(stream.iter_batches(batch_size=2, arrow=False))
+    assert [len(b) for b in batches] == [2, 2, 1]
+    assert batches[2][0].content == "Message 4"
+
+    with pytest.raises(ValueError):
+        stream.iter_batches(batch_size=0)
+
+
+def test_streaming_arrow_batches(tmp_path):
+    """Test Arrow record batches from a stream"""
+    pa = pytest.importorskip("pyarrow")
+
+    path = tmp_path / "chat.txt"
+    path.write_text(WHATSAPP_SAMPLE)
+
+    batches = list(chatpack.WhatsAppStreamParser(str(path)).iter_batches())
+    assert len(batches) == 1
+    assert isinstance(batches[0], pa.RecordBatch)
+    assert batches[0].column("sender").to_pylist() == ["Alice", "Bob"]
+    assert batches[0].schema.field("timestamp").type == pa.timestamp("ms", tz="UTC")
+
+
+def test_to_arrow():
+    """Test Arrow tables from messages and straight from parsing"""
+    pa = pytest.importorskip("pyarrow")
+
+    messages = chatpack.parse_whatsapp(WHATSAPP_SAMPLE.encode("utf-8"))
+    table = chatpack.to_arrow(messages)
+    assert isinstance(table, pa.Table)
+    assert table.column_names == [
+        "sender",
+        "content",
+        "timestamp",
+        "platform",
+        "id",
+        "reply_to",
+        "edited",
+        "conversation",
+    ]
+    assert table.column("content").to_pylist() == ["Hello", "Hi there"]
+
+    parsed = chatpack.parse_whatsapp_to_arrow(WHATSAPP_SAMPLE.encode("utf-8"))
+    assert parsed.equals(table)
+    assert chatpack.parse_auto_to_arrow(WHATSAPP_SAMPLE.encode("utf-8")).num_rows == 2
+    data = b"[1/15/24, 10:30:45 AM] Alice: bob@example.com"
+    redacted = chatpack.parse_whatsapp_to_arrow(data, redact=True)
+    assert redacted.column("content").to_pylist() == ["<EMAIL_1>"]
+    assert chatpack.to_arrow([]).num_rows == 0
+
+    pd = pytest.importorskip("pandas")
+    df = chatpack.to_pandas(messages)
+    assert isinstance(df, pd.DataFrame)
+    assert list(df["sender"]) == ["Alice", "Bob"]
+
+
 def test_merge_consecutive():
     """Test merging consecutive messages"""
     messages = [
@@ -100,6 +299,50 @@ def test_merge_consecutive():
     assert merged[0].sender == "Alice"
 
 
+def test_merge_time_threshold():
+    """Test that merging only joins messages within the time threshold"""
+    messages = [
+        chatpack.Message("Alice", "One", timestamp="2024-01-15T10:00:00+00:00"),
+        chatpack.Message("Alice", "Two", timestamp="2024-01-15T10:04:00+00:00"),
+        chatpack.Message("Alice", "Three", timestamp="2024-01-15T10:20:00+00:00"),
+    ]
+
+    merged = chatpack.merge_consecutive(messages, time_threshold=300)
+    assert [m.content for m in merged] == ["One\nTwo", "Three"]
+    assert merged[0].timestamp_str == "2024-01-15T10:00:00+00:00"
+
+    merged = chatpack.merge_consecutive(messages, time_threshold=None)
+    assert len(merged) == 1
+
+
+def test_merge_length_and_separator():
+    """Test max_merged_length and custom separator"""
+    messages = [
+        chatpack.Message("Alice", "aaaa"),
+        chatpack.Message("Alice", "bbbb"),
+        chatpack.Message("Alice", "cccc"),
+    ]
+
+    merged = chatpack.merge_consecutive(messages, max_merged_length=11, separator=" | ")
+    assert [m.content for m in merged] == ["aaaa | bbbb", "cccc"]
+
+
+def test_parse_with_merge_config(tmp_path):
+    """Test passing a MergeConfig as merge= argument"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Alice: Again"
+    )
+
+    parser = chatpack.WhatsAppParser()
+    merged = parser.parse(str(path), merge=chatpack.MergeConfig(separator=" "))
+    assert [m.content for m in merged] == ["Hello Again"]
+
+    strict = chatpack.MergeConfig(time_threshold=5)
+    assert len(chatpack.parse_whatsapp(str(path), merge=strict)) == 2
+    assert len(chatpack.parse_whatsapp(str(path), merge=True)) == 1
+
+
 def test_apply_filters():
     """Test applying filters to messages"""
     messages = [
@@ -116,6 +359,46 @@ def test_apply_filters():
     assert all(len(msg.content) >= 10 for msg in filtered)
 
 
+def test_apply_filters_max_length():
+    """Test that max_length drops long messages"""
+    messages = [
+        chatpack.Message("Alice", "Short"),
+        chatpack.Message("Bob", "This message is definitely too long"),
+    ]
+
+    filtered = chatpack.apply_filters(messages, chatpack.FilterConfig(max_length=10))
+
+    assert [m.content for m in filtered] == ["Short"]
+
+
+def test_apply_filters_truncate():
+    """Test truncate mode trims long messages with an ellipsis"""
+    messages = [
+        chatpack.Message("Alice", "Short"),
+        chatpack.Message("Bob", "This message is definitely too long"),
+    ]
+
+    config = chatpack.FilterConfig(max_length=10).with_truncate()
+    filtered = chatpack.apply_filters(messages, config)
+
+    assert len(filtered) == 2
+    assert filtered[1].content == "This mess…"
+    assert len(filtered[1].content) <= 10
+
+
+def test_parse_max_length(tmp_path):
+    """Test max_length on parse entry points"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:45 AM] Alice: Hi\n"
+        "[1/15/24, 10:31:00 AM] Bob: A much longer reply here"
+    )
+
+    messages = chatpack.parse_whatsapp(str(path), max_length=5)
+
+    assert [m.sender for m in messages] == ["Alice"]
+
+
 def test_whatsapp_parse_str():
     """Test parsing WhatsApp content from string"""
     parser = chatpack.WhatsAppParser()
@@ -132,6 +415,571 @@ def test_whatsapp_parse_str():
     assert messages[1].content == "Hi there"
 
 
+def test_configured_parser():
+    """Test that parser instances apply their stored configuration"""
+    parser = chatpack.WhatsAppParser(
+        filter=chatpack.FilterConfig(min_length=3),
+        merge=True,
+        output=chatpack.OutputConfig(include_timestamps=False, include_platform=True),
+    )
+    content = (
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n"
+        "[1/15/24, 10:31:00 AM] Alice: Again\n"
+        "[1/15/24, 10:32:00 AM] Bob: Hi"
+    )
+
+    messages = parser.parse_str(content)
+    assert [m.content for m in messages] == ["Hello\nAgain"]
+
+    from_bytes = parser.parse_bytes(content.encode("utf-8"))
+    assert [m.content for m in from_bytes] == ["Hello\nAgain"]
+
+    csv_text = parser.render(messages)
+    assert csv_text.splitlines()[0] == "sender,content,platform"
+
+
+def test_configured_parser_overrides(tmp_path):
+    """Test that per-call parse arguments override stored configuration"""
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Alice: Again"
+    )
+
+    parser = chatpack.WhatsAppParser(merge=True)
+
+    assert len(parser.parse(str(path))) == 1
+    assert len(parser.parse(str(path), merge=False)) == 2
+
+
+def test_parse_str_pipeline():
+    """Test that parse_str applies filters and merging like parse"""
+    parser = chatpack.WhatsAppParser()
+    content = (
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n"
+        "[1/15/24, 10:31:00 AM] Alice: Again\n"
+        "[1/16/24, 09:00:00 AM] Bob: Next day"
+    )
+
+    merged = parser.parse_str(content, merge=True)
+    assert [m.content for m in merged] == ["Hello\nAgain", "Next day"]
+
+    long_only = parser.parse_str(content, min_length=6)
+    assert [m.sender for m in long_only] == ["Bob"]
+
+    dated = parser.parse_str(content, date_from="2024-01-16", date_to="2024-01-16")
+    assert [m.sender for m in dated] == ["Bob"]
+
+
+WHATSAPP_SAMPLE = (
+    "[1/15/24, 10:30:45 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Bob: Hi there"
+)
+
+
+def test_parse_in_threads():
+    """Test parsing concurrently from a thread pool (the GIL is released)"""
+    from concurrent.futures import ThreadPoolExecutor
+
+    data = "".join(
+        f"[1/15/24, 10:{i % 60:02d}:00 AM] User{i % 3}: Message {i}\n"
+        for i in range(2000)
+    ).encode("utf-8")
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(chatpack.parse_whatsapp, [data] * 8))
+
+    assert all(len(r) == 2000 for r in results)
+    assert results[7][1999].content == "Message 1999"
+
+
+def test_async_parse(tmp_path):
+    """Test async_parse_* coroutines and async iteration over stream parsers"""
+    import asyncio
+
+    path = tmp_path / "chat.txt"
+    path.write_text(WHATSAPP_SAMPLE)
+
+    async def main():
+        whatsapp, auto = await asyncio.gather(
+            chatpack.async_parse_whatsapp(WHATSAPP_SAMPLE.encode("utf-8")),
+            chatpack.async_parse_auto(path, min_length=6),
+        )
+        assert [m.sender for m in whatsapp] == ["Alice", "Bob"]
+        assert [m.content for m in auto] == ["Hi there"]
+
+        streamed = [m.content async for m in chatpack.WhatsAppStreamParser(str(path))]
+        assert streamed == ["Hello", "Hi there"]
+
+        with pytest.raises(chatpack.ParseError):
+            await chatpack.async_parse_telegram(b"{ not json")
+
+        task = asyncio.create_task(
+            chatpack.async_parse_whatsapp(str(path), redact=chatpack.Redactor())
+        )
+        assert len(await task) == 2
+
+    asyncio.run(main())
+
+    # A coroutine, not a future bound to an already running loop
+    messages = asyncio.run(chatpack.async_parse_whatsapp(str(path), min_length=6))
+    assert [m.content for m in messages] == ["Hi there"]
+
+
+def test_parse_from_bytes_and_buffers():
+    """Test parsing from bytes, bytearray and memoryview"""
+    data = WHATSAPP_SAMPLE.encode("utf-8")
+
+    for source in (data, bytearray(data), memoryview(data)):
+        messages = chatpack.parse_whatsapp(source)
+        assert [m.sender for m in messages] == ["Alice", "Bob"]
+
+
+def test_parse_from_file_like():
+    """Test parsing from objects with .read()"""
+    import io
+
+    binary = chatpack.parse_whatsapp(io.BytesIO(WHATSAPP_SAMPLE.encode("utf-8")))
+    text = chatpack.WhatsAppParser().parse(io.StringIO(WHATSAPP_SAMPLE))
+
+    assert len(binary) == 2
+    assert [m.content for m in text] == ["Hello", "Hi there"]
+
+
+def test_parse_from_pathlib(tmp_path):
+    """Test parsing from a pathlib.Path"""
+    path = tmp_path / "chat.txt"
+    path.write_text(WHATSAPP_SAMPLE)
+
+    messages = chatpack.parse_whatsapp(path, min_length=3)
+
+    assert [m.content for m in messages] == ["Hello", "Hi there"]
+
+
+def test_parse_invalid_source():
+    """Test that unsupported source types raise TypeError"""
+    with pytest.raises(TypeError):
+        chatpack.parse_whatsapp(42)
+
+
+TELEGRAM_SAMPLE = {
+    "name": "Test Chat",
+    "type": "personal_chat",
+    "messages": [
+        {
+            "id": 1,
+            "type": "message",
+            "date": "2024-01-15T10:30:00",
+            "date_unixtime": "1705314600",
+            "from": "Alice",
+            "text": "Hello!",
+        }
+    ],
+}
+
+INSTAGRAM_SAMPLE = {
+    "participants": [{"name": "user_one"}, {"name": "user_two"}],
+    "messages": [
+        {
+            "sender_name": "user_one",
+            "timestamp_ms": 1705315800000,
+            "content": "Hey! How are you?",
+        }
+    ],
+}
+
+DISCORD_SAMPLE = {
+    "guild": {"id": "1", "name": "Test Server"},
+    "channel": {"id": "2", "name": "general"},
+    "messages": [
+        {
+            "id": "1001",
+            "type": "Default",
+            "timestamp": "2024-01-15T10:30:00+00:00",
+            "content": "Hello Discord!",
+            "author": {"id": "111", "name": "alice", "nickname": "Alice"},
+        }
+    ],
+}
+
+
+def test_detect_platform():
+    """Test platform detection from content"""
+    import json
+
+    samples = [
+        (json.dumps(TELEGRAM_SAMPLE), chatpack.Platform.TELEGRAM),
+        (json.dumps(INSTAGRAM_SAMPLE, indent=2), chatpack.Platform.INSTAGRAM),
+        (json.dumps(DISCORD_SAMPLE), chatpack.Platform.DISCORD),
+        (WHATSAPP_SAMPLE, chatpack.Platform.WHATSAPP),
+        ("AuthorID,Author,Date,Content\n", chatpack.Platform.DISCORD),
+    ]
+
+    for content, expected in samples:
+        platform, confidence = chatpack.detect_platform(content.encode("utf-8"))
+        assert platform == expected
+        assert 0.5 <= confidence <= 1.0
+
+    assert chatpack.detect_platform(b"just some notes") == (None, 0.0)
+
+
+def test_parse_auto(tmp_path):
+    """Test parse_auto dispatches to the detected parser"""
+    import json
+
+    path = tmp_path / "message_1.json"
+    path.write_text(json.dumps(INSTAGRAM_SAMPLE))
+
+    messages = chatpack.parse_auto(path, min_length=3)
+    assert [m.sender for m in messages] == ["user_one"]
+    assert messages[0].platform == "instagram"
+
+    whatsapp = chatpack.parse_auto(WHATSAPP_SAMPLE.encode("utf-8"), merge=True)
+    assert [m.platform for m in whatsapp] == ["whatsapp", "whatsapp"]
+
+    with pytest.raises(ValueError):
+        chatpack.parse_auto(b"just some notes")
+
+
+def test_parse_many(tmp_path):
+    """Test parsing several files in parallel, collecting per-file errors"""
+    import json
+
+    telegram = tmp_path / "result.json"
+    telegram.write_text(json.dumps(TELEGRAM_SAMPLE))
+    whatsapp = tmp_path / "chat.txt"
+    whatsapp.write_text(WHATSAPP_SAMPLE)
+    missing = tmp_path / "missing.json"
+
+    messages, errors = chatpack.parse_many([whatsapp, telegram, missing], workers=2)
+    assert [m.sender for m in messages] == ["Alice", "Alice", "Bob"]
+    assert [m.platform for m in messages] == ["telegram", "whatsapp", "whatsapp"]
+    assert list(errors) == [str(missing)]
+    assert isinstance(errors[str(missing)], chatpack.ChatpackIOError)
+
+    by_path, errors = chatpack.parse_many(
+        [str(whatsapp)], platform="whatsapp", by_path=True, merge=True
+    )
+    assert list(by_path) == [str(whatsapp)]
+    assert len(by_path[str(whatsapp)]) == 2
+    assert errors == {}
+
+    _, errors = chatpack.parse_many([whatsapp], platform="telegram")
+    assert isinstance(errors[str(whatsapp)], chatpack.ParseError)
+
+
+def test_parse_instagram_directory(tmp_path):
+    """Test stitching message_N.json parts of Instagram conversations"""
+    import json
+
+    def part(title, *messages):
+        return json.dumps(
+            {
+                "title": title,
+                "participants": [{"name": title.lower()}, {"name": "me"}],
+                "messages": [
+                    {"sender_name": sender, "timestamp_ms": ts, "content": content}
+                    for sender, ts, content in messages
+                ],
+            }
+        )
+
+    # message_1.json holds the newest messages, each file is newest-first
+    chat = tmp_path / "inbox" / "alice_123"
+    chat.mkdir(parents=True)
+    (chat / "message_1.json").write_text(
+        part("Alice", ("alice", 4000, "Four"), ("me", 3000, "Three"))
+    )
+    (chat / "message_2.json").write_text(
+        part(
+            "Alice",
+            ("me", 3000, "Three"),
+            ("alice", 2000, "Two"),
+            ("alice", 1000, "One"),
+        )
+    )
+    other = tmp_path / "inbox" / "bob_456"
+    other.mkdir()
+    (other / "message_1.json").write_text(part("Bob", ("bob", 500, "Hey")))
+
+    messages = chatpack.parse_instagram(chat)
+    assert [m.content for m in messages] == ["One", "Two", "Three", "Four"]
+    assert {m.conversation for m in messages} == {"Alice"}
+
+    messages = chatpack.parse_instagram(str(tmp_path / "inbox"), merge=True)
+    assert [(m.conversation, m.content) for m in messages] == [
+        ("Alice", "One\nTwo"),
+        ("Alice", "Three"),
+        ("Alice", "Four"),
+        ("Bob", "Hey"),
+    ]
+
+    assert chatpack.detect_platform(chat) == (chatpack.Platform.INSTAGRAM, 1.0)
+    assert len(chatpack.parse_auto(chat)) == 4
+
+    # Repeats inside one part are real messages
+    repeats = tmp_path / "carol_789"
+    repeats.mkdir()
+    (repeats / "message_1.json").write_text(
+        part("Carol", ("carol", 2000, "ok"), ("carol", 2000, "ok"))
+    )
+    assert len(chatpack.parse_instagram(repeats)) == 2
+
+    pytest.importorskip("pyarrow")
+    table = chatpack.parse_instagram_to_arrow(str(tmp_path / "inbox"))
+    assert table.column("conversation").to_pylist() == ["Alice"] * 4 + ["Bob"]
+
+
+def test_parse_zip_exports(tmp_path):
+    """Test parsing zipped exports from a path or bytes"""
+    import io
+    import json
+    import zipfile
+
+    chat = (
+        "[1/15/24, 10:30:45 AM] Alice: <attached: IMG-0001.jpg>\n"
+        "[1/15/24, 10:31:00 AM] Bob: Nice photo"
+    )
+    path = tmp_path / "WhatsApp Chat - Alice.zip"
+    with zipfile.ZipFile(path, "w", zipfile.ZIP_DEFLATED) as archive:
+        archive.writestr("_chat.txt", chat)
+        archive.writestr("IMG-0001.jpg", b"\xff\xd8\xff")
+        archive.writestr("AUDIO-0002.opus", b"OggS")
+        archive.writestr("__MACOSX/._chat.txt", b"\x00\x05")
+
+    messages = chatpack.parse_whatsapp(path)
+    assert [m.sender for m in messages] == ["Alice", "Bob"]
+    assert chatpack.detect_platform(path)[0] == chatpack.Platform.WHATSAPP
+    assert len(chatpack.parse_auto(path.read_bytes(), min_length=11)) == 1
+    assert len(chatpack.WhatsAppParser().parse_bytes(path.read_bytes())) == 2
+    assert chatpack.parse_many([path])[0][1].content == "Nice photo"
+    assert chatpack.referenced_media(path, messages) == {"IMG-0001.jpg": [0]}
+
+    with pytest.raises(chatpack.UnsupportedFormatError):
+        chatpack.parse_telegram(path)
+
+    # Line-based chats are parsed while they are decompressed, like plain files
+    multiline = (
+        "[1/15/24, 10:30:45 AM] Alice: First line\n"
+        "second line\r\n"
+        "\n"
+        "[1/15/24, 10:31:00 AM] Bob: Hi\n"
+    )
+    jsonl = (
+        '{"id": "1", "timestamp": "2024-01-15T10:30:00+00:00", "content": "Hey",'
+        ' "author": {"name": "alice"}}\n'
+        '{"id": "2", "timestamp": "2024-01-15T10:31:00+00:00", "content": "Yo",'
+        ' "author": {"name": "bob"}, "reference": {"messageId": "1"}}\n'
+    )
+    streamed = tmp_path / "streamed.zip"
+    with zipfile.ZipFile(streamed, "w", zipfile.ZIP_DEFLATED) as archive:
+        archive.writestr("_chat.txt", multiline)
+        archive.writestr("general.jsonl", jsonl)
+
+    plain = chatpack.parse_whatsapp(multiline.encode())
+    zipped = chatpack.parse_whatsapp(streamed, merge=True)
+    assert [m.content for m in zipped] == ["First line\nsecond line", "Hi"]
+    assert [m.content for m in zipped] == [m.content for m in plain]
+    discord = chatpack.parse_discord(streamed)
+    assert [(m.sender, m.reply_to) for m in discord] == [("alice", None), ("bob", 1)]
+
+    instagram = io.BytesIO()
+    with zipfile.ZipFile(instagram, "w", zipfile.ZIP_DEFLATED) as archive:
+        inbox = "your_instagram_activity/messages/inbox/alice_123/"
+        for n, (ts, content) in enumerate([(2000, "Two"), (1000, "One")], 1):
+            part = {
+                "title": "Alice",
+                "participants": [{"name": "alice"}, {"name": "me"}],
+                "messages": [
+                    {"sender_name": "alice", "timestamp_ms": ts, "content": content}
+                ],
+            }
+            archive.writestr(f"{inbox}message_{n}.json", json.dumps(part))
+
+    messages = chatpack.parse_auto(instagram.getvalue())
+    assert [(m.conversation, m.content) for m in messages] == [
+        ("Alice", "One"),
+        ("Alice", "Two"),
+    ]
+
+
+def test_zip_size_limit():
+    """Test that oversized archive entries are refused"""
+    import io
+    import struct
+    import zipfile
+
+    buffer = io.BytesIO()
+    with zipfile.ZipFile(buffer, "w", zipfile.ZIP_DEFLATED) as archive:
+        archive.writestr("_chat.txt", "[1/15/24, 10:30:45 AM] Alice: Hi")
+
+    # Claim 2 GiB of decompressed data in the central directory
+    data = bytearray(buffer.getvalue())
+    struct.pack_into("<I", data, data.rindex(b"PK\x01\x02") + 24, 2**31)
+
+    with pytest.raises(chatpack.ChatpackIOError, match="size limit"):
+        chatpack.parse_whatsapp(bytes(data))
+
+
+def test_token_counting():
+    """Test token counts and token-budget chunking"""
+    messages = [
+        chatpack.Message("Alice" if i % 2 else "Bob", f"Message number {i}", id=i)
+        for i in range(10)
+    ]
+
+    total = chatpack.count_tokens(messages)
+    assert total > chatpack.count_tokens(messages[:5]) > 0
+    assert chatpack.count_tokens(messages, tokenizer="o200k") > 0
+    rendered = chatpack.to_csv_string(messages)
+    estimate = (len(rendered) + 3) // 4
+    assert chatpack.count_tokens(messages, tokenizer="chars") == estimate
+    assert chatpack.count_tokens(messages, tokenizer=lambda text: 7) == 7
+
+    chunks = chatpack.chunk_by_tokens(messages, max_tokens=total // 2)
+    assert len(chunks) > 1
+    assert [m.id for chunk in chunks for m in chunk] == list(range(10))
+    assert all(chatpack.count_tokens(chunk) <= total // 2 for chunk in chunks)
+
+    overlapping = chatpack.chunk_by_tokens(
+        messages, max_tokens=total // 2, overlap_messages=1
+    )
+    for previous, chunk in zip(overlapping, overlapping[1:]):
+        assert chunk[0].id == previous[-1].id
+
+    with pytest.raises(ValueError):
+        chatpack.count_tokens(messages, tokenizer="gpt2")
+
+
+def test_to_documents():
+    """Test document windows follow sessions and carry metadata"""
+    start = datetime(2024, 1, 15, 10, 0, tzinfo=timezone.utc)
+    minutes = [0, 1, 2, 3, 10, 11, 12, 13, 14, 15, 300, 301, 302]
+    messages = [
+        chatpack.Message(
+            "Alice" if i % 2 else "Bob",
+            f"Message {i}",
+            timestamp=start + timedelta(minutes=m),
+            platform="telegram",
+            id=i,
+        )
+        for i, m in enumerate(minutes)
+    ]
+
+    docs = chatpack.to_documents(messages, window=6, stride=4, source="chat.json")
+    assert [(d.start_id, d.end_id) for d in docs] == [(0, 3), (2, 7), (6, 9), (10, 12)]
+    assert docs[0].participants == ["Bob", "Alice"]
+    assert docs[0].start_ts == start
+    assert docs[-1].end_ts == start + timedelta(minutes=302)
+    assert docs[0].platform == "telegram"
+    assert docs[0].source == "chat.json"
+    assert docs[0].text == chatpack.to_csv_string(messages[:4])
+    assert len(docs[0]) == docs[0].to_dict()["message_count"] == 4
+
+    docs = chatpack.to_documents(messages, idle_gap=timedelta(hours=6))
+    assert len(docs) == 1 and docs[0].message_count == len(messages)
+
+    with pytest.raises(ValueError):
+        chatpack.to_documents(messages, window=4, stride=5)
+
+
+def test_split_sessions(tmp_path):
+    """Test session splitting on lists and stream parsers"""
+    start = datetime(2024, 1, 15, 10, 0, tzinfo=timezone.utc)
+    messages = [
+        chatpack.Message("Alice", "Hi", timestamp=start),
+        chatpack.Message("Bob", "Hello", timestamp=start + timedelta(minutes=30)),
+        chatpack.Message("Alice", "Undated"),
+        chatpack.Message("Alice", "Later", timestamp=start + timedelta(hours=5)),
+    ]
+
+    sessions = chatpack.split_sessions(messages)
+    assert [len(s) for s in sessions] == [3, 1]
+    assert [m.content for m in sessions[0]] == ["Hi", "Hello", "Undated"]
+    assert sessions[0].duration == timedelta(minutes=30)
+    assert sessions[0].participants == {"Alice": 2, "Bob": 1}
+    assert sessions[0].to_dict()["participant_count"] == 2
+    assert sessions[1].start_ts == start + timedelta(hours=5)
+
+    assert len(chatpack.split_sessions(messages, gap=timedelta(minutes=10))) == 3
+    with pytest.raises(ValueError):
+        chatpack.split_sessions(messages, gap=timedelta(0))
+
+    path = tmp_path / "chat.txt"
+    path.write_text(
+        "[1/15/24, 10:30:45 AM] Alice: Hello\n"
+        "[1/15/24, 10:31:00 AM] Bob: Hi there\n"
+        "[1/16/24, 9:00:00 AM] Alice: Good morning everyone\n"
+    )
+    stream = chatpack.WhatsAppStreamParser(str(path))
+    streamed = list(stream.iter_sessions())
+    assert [s.message_count for s in streamed] == [2, 1]
+    assert streamed[0].messages[0].platform == "whatsapp"
+
+
+def test_redaction(tmp_path):
+    """Test PII detectors, strategies and the parse-time option"""
+    messages = [
+        chatpack.Message("Alice", "Call +7 (912) 345-67-89 or mail bob@example.com"),
+        chatpack.Message("Bob", "Card 4111 1111 1111 1111, DE89370400440532013000"),
+        chatpack.Message("Alice", "https://x.io/?token=s3cr3t @carol +7 912 345 67 89"),
+        chatpack.Message("Bob", "Order 123456 on 2024-01-15 https://example.com/page"),
+    ]
+
+    redacted = chatpack.redact(messages)
+    assert [m.content for m in redacted] == [
+        "Call <PHONE_1> or mail <EMAIL_1>",
+        "Card <CARD_1>, <IBAN_1>",
+        "<URL_1> <MENTION_1> <PHONE_1>",
+        "Order 123456 on 2024-01-15 https://example.com/page",
+    ]
+    assert messages[0].content.startswith("Call +7")
+
+    redactor = chatpack.Redactor(
+        patterns={"ticket": r"JIRA-\d+"}, detectors=["email"]
+    )
+    text = redactor.redact_text("JIRA-7 by bob@example.com")
+    assert text == "<TICKET_1> by <EMAIL_1>"
+    assert redactor.redact_text("again JIRA-7") == "again <TICKET_1>"
+    assert redactor.mapping["<TICKET_1>"] == "JIRA-7"
+    assert redactor.restore("Fixed <TICKET_1>") == "Fixed JIRA-7"
+    redactor.reset()
+    assert redactor.redact_text("JIRA-8") == "<TICKET_1>"
+
+    masked = chatpack.Redactor(strategy="mask").redact_text("mail bob@example.com")
+    assert masked == "mail " + "*" * len("bob@example.com")
+    hashed = chatpack.Redactor(strategy="hash", salt="x")
+    digest = hashed.redact_text("bob@example.com")
+    assert digest == hashed.redact_text("BOB@example.com")
+    assert digest.startswith("<EMAIL:")
+    assert chatpack.Redactor(strategy="hash").redact_text("bob@example.com") != digest
+    with pytest.raises(ValueError):
+        chatpack.Redactor(strategy="hash", salt="")
+
+    data = b"[1/15/24, 10:30:45 AM] +1 555 123 4567: mail me at bob@example.com"
+    parsed = chatpack.parse_whatsapp(data, redact=True)
+    assert parsed[0].content == "mail me at <EMAIL_1>"
+    assert parsed[0].sender == "+1 555 123 4567"
+    senders = chatpack.Redactor(senders=True)
+    assert chatpack.parse_auto(data, redact=senders)[0].sender == "<PHONE_1>"
+    titled = chatpack.Message("me", "hi", conversation="+1 555 123 4567")
+    assert senders.redact([titled])[0].conversation == "<PHONE_1>"
+
+    # Every entry point takes redact=
+    parser = chatpack.WhatsAppParser(redact=True)
+    assert parser.parse_bytes(data)[0].content == "mail me at <EMAIL_1>"
+    assert parser.parse_str(data.decode(), redact=False)[0].content.endswith(".com")
+    path = tmp_path / "chat.txt"
+    path.write_bytes(data)
+    stream = chatpack.WhatsAppStreamParser(str(path), redact=senders)
+    assert [m.sender for m in stream] == ["<PHONE_1>"]
+    messages, _ = chatpack.parse_many([path], redact=True)
+    assert messages[0].content == "mail me at <EMAIL_1>"
+
+    with pytest.raises(ValueError):
+        chatpack.Redactor(strategy="encrypt")
+    with pytest.raises(ValueError):
+        chatpack.Redactor(detectors=["ssn"])
+
+
 def test_message_repr():
     """Test Message string representation"""
     msg = chatpack.Message("Alice", "This is a very long message content")
@@ -150,5 +998,107 @@ def test_message_str():
     assert "Hello" in str_msg
 
 
+def test_to_csv_string():
+    """Test rendering messages as CSV"""
+    messages = [
+        chatpack.Message("Alice", "Hello", timestamp="2024-01-15T10:30:00+00:00"),
+        chatpack.Message("Bob", "Hi, there"),
+    ]
+
+    csv_text = chatpack.to_csv_string(messages)
+    lines = csv_text.strip().splitlines()
+
+    assert lines[0] == "sender,content,timestamp"
+    assert lines[1] == "Alice,Hello,2024-01-15T10:30:00+00:00"
+    assert lines[2] == 'Bob,"Hi, there",'
+
+
+def test_output_config_columns():
+    """Test that OutputConfig controls emitted columns"""
+    messages = [chatpack.Message("Alice", "Hello", platform="telegram")]
+    config = chatpack.OutputConfig(include_timestamps=False, include_platform=True)
+
+    assert chatpack.to_csv_string(messages, config).splitlines()[0] == (
+        "sender,content,platform"
+    )
+    assert chatpack.to_jsonl_string(messages, config) == (
+        '{"sender":"Alice","content":"Hello","platform":"telegram"}\n'
+    )
+
+
+def test_write_outputs(tmp_path):
+    """Test writing messages to CSV, JSON and JSONL files"""
+    import json
+
+    messages = [
+        chatpack.Message("Alice", "Hello"),
+        chatpack.Message("Bob", "Hi"),
+    ]
+
+    chatpack.write_csv(messages, str(tmp_path / "out.csv"))
+    chatpack.write_json(messages, str(tmp_path / "out.json"))
+    chatpack.write_jsonl(messages, str(tmp_path / "out.jsonl"))
+
+    assert (tmp_path / "out.csv").read_text().startswith("sender,content")
+    data = json.loads((tmp_path / "out.json").read_text())
+    assert [m["sender"] for m in data] == ["Alice", "Bob"]
+    lines = (tmp_path / "out.jsonl").read_text().splitlines()
+    assert json.loads(lines[1])["content"] == "Hi"
+
+
+def test_write_parquet(tmp_path):
+    """Test Parquet output from messages and from a stream"""
+    messages = chatpack.parse_whatsapp(WHATSAPP_SAMPLE.encode("utf-8"))
+    path = tmp_path / "messages.parquet"
+    chatpack.write_parquet(messages, path)
+
+    data = path.read_bytes()
+    assert data[:4] == b"PAR1" and data[-4:] == b"PAR1"
+
+    source = tmp_path / "chat.txt"
+    source.write_text(WHATSAPP_SAMPLE)
+    stream = chatpack.WhatsAppStreamParser(str(source))
+    streamed = tmp_path / "streamed.parquet"
+    written = chatpack.write_parquet_stream(
+        stream, streamed, compression="snappy", row_group_size=1
+    )
+    assert written == 2
+
+    with pytest.raises(chatpack.UnsupportedFormatError):
+        chatpack.write_parquet(messages, path, compression="rar")
+
+    pq = pytest.importorskip("pyarrow.parquet")
+    table = pq.read_table(path)
+    assert table.column("sender").to_pylist() == ["Alice", "Bob"]
+    assert pq.ParquetFile(streamed).num_row_groups == 2
+
+
+def test_error_hierarchy(tmp_path):
+    """Test failures raise ChatpackError subclasses with context"""
+    missing = tmp_path / "missing.json"
+    with pytest.raises(chatpack.ChatpackIOError) as exc:
+        chatpack.parse_telegram(missing)
+    assert isinstance(exc.value, OSError)
+    assert isinstance(exc.value, chatpack.ChatpackError)
+    assert exc.value.path == str(missing)
+
+    broken = tmp_path / "result.json"
+    broken.write_text('{\n  "messages": [\n    {"id": 1,\n')
+    with pytest.raises(chatpack.ParseError) as exc:
+        chatpack.parse_telegram(broken)
+    assert exc.value.platform == "telegram"
+    assert exc.value.line == 4
+
+    with pytest.raises(chatpack.InvalidDateError):
+        chatpack.parse_whatsapp(WHATSAPP_SAMPLE.encode("utf-8"), date_from="yesterday")
+
+    with pytest.raises(chatpack.UnsupportedFormatError):
+        chatpack.TelegramParser().render([], "xml")
+    with pytest.raises(chatpack.UnsupportedFormatError):
+        chatpack.Platform.from_str("myspace")
+    with pytest.raises(chatpack.UnsupportedFormatError):
+        chatpack.Message("Alice", "Hello", platform="myspace")
+
+
 if __name__ == "__main__":
     pytest.main([__file__, "-v"])